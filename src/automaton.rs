@@ -0,0 +1,36 @@
+use std::io;
+
+/// Operations shared by all automata, so that a simulation can be driven
+/// (e.g. by `run` or `GifRecorder::push_automaton`) without knowing
+/// which automaton it is.
+///
+/// The inherent methods of the same name are still available,
+/// this trait only needs to be in scope for generic code.
+pub trait Automaton {
+    /// Calculate next state of the automaton
+    fn next(&mut self);
+
+    /// Output current state to a file
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()>;
+
+    /// Number of lines and columns of the images produced
+    fn dimensions(&self) -> (usize, usize);
+
+    /// Width, height and RGB pixels (3 bytes per pixel, line by line)
+    /// of the current state, one pixel per cell
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>);
+}
+
+/// Output the current state then calculate the next one,
+/// `generations` times
+pub fn run(
+    automaton: &mut dyn Automaton,
+    cfg: &mut crate::Config,
+    generations: usize,
+) -> io::Result<()> {
+    for _ in 0..generations {
+        automaton.render(cfg)?;
+        automaton.next();
+    }
+    Ok(())
+}
@@ -1,5 +1,7 @@
 use rand::Rng;
+use std::io;
 
+use crate::automaton::Automaton;
 use crate::canvas::*;
 
 /// A cell in Brian's brain can have any of three states
@@ -33,8 +35,8 @@ impl Colorize for Neuron {
     fn color(&self) -> Color {
         match self.curr {
             State::Ready => (0, 0, 0),
-            State::Firing => (25, 25, 25),
-            State::Dying => (0, 0, 25),
+            State::Firing => (255, 255, 255),
+            State::Dying => (0, 0, 255),
         }
     }
 }
@@ -101,75 +103,17 @@ impl Brain {
         }
     }
 
-    /// 2D Array access with automatic looping around the edges.
-    /// Only works for direct neighbors (Moore neighborhood)
-    fn index_move(&self, i: usize, j: usize, mvi: isize, mvj: isize) -> [usize; 2] {
-        let (mut i, mut j) = (i, j);
-        match mvi {
-            -1 => {
-                if i == 0 {
-                    i = self.hgt - 1;
-                } else {
-                    i -= 1;
-                }
-            }
-            1 => {
-                if i == self.hgt - 1 {
-                    i = 0
-                } else {
-                    i += 1;
-                }
-            }
-            0 => (),
-            _ => panic!("({}, {}) is not a neighbor: abs({}) > 1", mvi, mvj, mvi),
-        }
-        match mvj {
-            -1 => {
-                if j == 0 {
-                    j = self.hgt - 1;
-                } else {
-                    j -= 1;
-                }
-            }
-            1 => {
-                if j == self.hgt - 1 {
-                    j = 0
-                } else {
-                    j += 1;
-                }
-            }
-            0 => (),
-            _ => panic!("({}, {}) is not a neighbor: abs({}) > 1", mvi, mvj, mvj),
-        }
-        [i, j]
-    }
-
     // Count neighbors (with looping around the edges) who are fired
     fn count_neigh(&self, i: usize, j: usize) -> usize {
         let mut res = 0;
-        if self.field[self.index_move(i, j, -1, 0)].is_firing() {
-            res += 1;
-        }
-        if self.field[self.index_move(i, j, -1, -1)].is_firing() {
-            res += 1;
-        }
-        if self.field[self.index_move(i, j, -1, 1)].is_firing() {
-            res += 1;
-        }
-        if self.field[self.index_move(i, j, 1, 0)].is_firing() {
-            res += 1;
-        }
-        if self.field[self.index_move(i, j, 1, -1)].is_firing() {
-            res += 1;
-        }
-        if self.field[self.index_move(i, j, 1, 1)].is_firing() {
-            res += 1;
-        }
-        if self.field[self.index_move(i, j, 0, -1)].is_firing() {
-            res += 1;
-        }
-        if self.field[self.index_move(i, j, 0, 1)].is_firing() {
-            res += 1;
+        for mvi in -1..=1 {
+            for mvj in -1..=1 {
+                if (mvi, mvj) != (0, 0)
+                    && self.field[self.field.index_move(i, j, mvi, mvj)].is_firing()
+                {
+                    res += 1;
+                }
+            }
         }
         res
     }
@@ -203,12 +147,40 @@ impl Brain {
         self.update();
     }
 
+    /// Current state as text: `#` for firing cells, `.` for dying ones
+    /// and spaces for ready ones
+    pub fn render_ascii(&self) -> String {
+        self.field.to_ascii_with(|c| match c.curr {
+            State::Ready => ' ',
+            State::Firing => '#',
+            State::Dying => '.',
+        })
+    }
+
     /// Output current state of the canvas as an image
-    pub fn render(&mut self, cfg: &mut crate::Config) {
-        let name = cfg.frame();
-        self.field.render(&name);
+    pub fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        let name = cfg.render(&self.field)?;
 
         eprint!("\rDone generation {} : {} fired", name, self.fired);
+        Ok(())
+    }
+}
+
+impl Automaton for Brain {
+    fn next(&mut self) {
+        Brain::next(self);
+    }
+
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        Brain::render(self, cfg)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        self.field.dimensions()
+    }
+
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.field.to_rgb_buffer()
     }
 }
 
@@ -1,15 +1,142 @@
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{self, Write};
 
 /// RGB color
-/// Each component should be in the range [0, 25]
+/// Each component is in the full range [0, 255]
 pub type Color = (u8, u8, u8);
 
+/// Linear interpolation between two colors, `t` should be in [0, 1]
+pub fn gradient(from: Color, to: Color, t: f64) -> Color {
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    (mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+/// Color of a heat value `t` in [0, 1],
+/// from black through red and yellow to white
+pub fn heat(t: f64) -> Color {
+    let t = t.clamp(0., 1.) * 3.;
+    match t as usize {
+        0 => gradient((0, 0, 0), (255, 0, 0), t),
+        1 => gradient((255, 0, 0), (255, 255, 0), t - 1.),
+        _ => gradient((255, 255, 0), (255, 255, 255), t - 2.),
+    }
+}
+
+/// Fully saturated color of hue `h`, in turns around the color wheel:
+/// red for 0, green for 1/3, blue for 2/3, and red again for 1
+pub fn hue(h: f64) -> Color {
+    const WHEEL: [Color; 7] = [
+        (255, 0, 0),
+        (255, 255, 0),
+        (0, 255, 0),
+        (0, 255, 255),
+        (0, 0, 255),
+        (255, 0, 255),
+        (255, 0, 0),
+    ];
+    let h = h.rem_euclid(1.) * 6.;
+    let k = (h as usize).min(5);
+    gradient(WHEEL[k], WHEEL[k + 1], h - k as f64)
+}
+
 /// Indicates that a object of a given type can be converted to a color.
 pub trait Colorize<T = Self>: Copy {
     fn color(&self) -> Color;
 }
 
+/// Image formats in which frames can be written
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// ASCII portable pixmap
+    Ppm,
+    /// Binary portable pixmap, much faster to write and smaller than `Ppm`
+    P6,
+    /// Requires the `image` feature
+    #[cfg(feature = "image")]
+    Png,
+}
+
+impl Format {
+    /// File extension associated with the format
+    pub fn ext(self) -> &'static str {
+        match self {
+            Format::Ppm | Format::P6 => "ppm",
+            #[cfg(feature = "image")]
+            Format::Png => "png",
+        }
+    }
+
+    /// Format of a file named `name`: PNG if it ends with `.png`
+    /// and the `image` feature is enabled, ASCII PPM otherwise
+    #[cfg_attr(not(feature = "image"), allow(unused_variables))]
+    pub fn of_file(name: &str) -> Self {
+        #[cfg(feature = "image")]
+        {
+            if name.ends_with(".png") {
+                return Format::Png;
+            }
+        }
+        Format::Ppm
+    }
+}
+
+/// Default color of the lines separating cells
+pub const GRID: Color = (64, 64, 64);
+
+/// Options for drawing a canvas
+#[derive(Clone, Copy, Debug)]
+pub struct Style {
+    /// Each cell is drawn as a block of `scale_y` lines
+    /// and `scale_x` columns of pixels
+    pub scale_x: usize,
+    pub scale_y: usize,
+    /// Color of the 1-pixel lines drawn between blocks.
+    /// Lines replace the first line and column of each block,
+    /// so they are only drawn along axes with a scale larger than 1.
+    pub grid: Option<Color>,
+    /// Gamma correction, see `set_gamma`
+    gamma: f64,
+    /// Corrected value of each channel value, `None` if `gamma` is 1
+    lut: Option<[u8; 256]>,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            scale_x: 1,
+            scale_y: 1,
+            grid: None,
+            gamma: 1.,
+            lut: None,
+        }
+    }
+}
+
+impl Style {
+    /// Gamma correction: each channel value `v` is drawn as
+    /// `255 * (v / 255) ^ (1 / gamma)`. 1 leaves colors unchanged,
+    /// larger values brighten the middle of gradients.
+    /// Pure black and full channels are never affected.
+    /// Values that are not positive are ignored.
+    pub fn set_gamma(&mut self, gamma: f64) {
+        self.gamma = gamma;
+        self.lut = if gamma != 1. && gamma > 0. {
+            let mut lut = [0; 256];
+            for (v, c) in lut.iter_mut().enumerate() {
+                *c = (255. * (v as f64 / 255.).powf(1. / gamma)).round() as u8;
+            }
+            Some(lut)
+        } else {
+            None
+        };
+    }
+
+    /// Gamma correction, see `set_gamma`
+    pub fn gamma(&self) -> f64 {
+        self.gamma
+    }
+}
+
 /// A canvas is a 2D array of objects that we know how to convert to colors
 pub struct Canvas<T: Colorize> {
     hgt: usize,
@@ -28,23 +155,355 @@ impl<T: Colorize> Canvas<T> {
         }
     }
 
-    /// Output the current state of the canvas to a file
-    pub fn render(&self, name: &str) {
-        let mut f = BufWriter::new(File::create(name).unwrap());
-        write!(f, "P3\n{} {}\n25\n", self.wth, self.hgt).unwrap();
+    /// Number of lines and columns
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.hgt, self.wth)
+    }
+
+    /// Output the current state of the canvas to a file.
+    /// The format is chosen from the extension of `name`, PPM by default.
+    pub fn render(&self, name: &str) -> io::Result<()> {
+        self.render_with(name, &Style::default(), |c| c.color())
+    }
+
+    /// Same as `render`, but drawn according to `style` and with colors
+    /// chosen by `color` instead of the `Colorize` implementation
+    pub fn render_with<F: Fn(&T) -> Color>(
+        &self,
+        name: &str,
+        style: &Style,
+        color: F,
+    ) -> io::Result<()> {
+        let format = Format::of_file(name);
+        self.render_with_buffer(name, format, style, color, &mut Vec::new(), &mut Vec::new())
+    }
+
+    /// Same as `render_with`, but written in `format` whatever the extension
+    /// of `name`, with the pixels drawn in `rgb` and the file contents
+    /// prepared in `out` (both cleared first) then written at once.
+    /// Passing the same buffers for every frame avoids reallocating them each time.
+    pub fn render_with_buffer<F: Fn(&T) -> Color>(
+        &self,
+        name: &str,
+        format: Format,
+        style: &Style,
+        color: F,
+        rgb: &mut Vec<u8>,
+        out: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        let (wth, hgt) = self.to_rgb_buffer_into(style, color, rgb);
+        out.clear();
+        match format {
+            Format::Ppm => {
+                out.reserve(rgb.len() * 4 + 32);
+                write!(out, "P3\n{} {}\n255\n", wth, hgt)?;
+                for &v in rgb.iter() {
+                    push_decimal(out, v);
+                }
+            }
+            Format::P6 => {
+                out.reserve(rgb.len() + 32);
+                write!(out, "P6\n{} {}\n255\n", wth, hgt)?;
+                out.extend_from_slice(rgb);
+            }
+            #[cfg(feature = "image")]
+            Format::Png => {
+                let img = image::RgbImage::from_raw(wth as u32, hgt as u32, std::mem::take(rgb))
+                    .expect("buffer has the size of the image");
+                let res = img.save(name).map_err(io::Error::other);
+                *rgb = img.into_raw();
+                return res;
+            }
+        }
+        File::create(name)?.write_all(out)
+    }
+
+    /// Output the current state of the canvas to a binary (P6) PPM file,
+    /// much faster to write and smaller than the ASCII version
+    pub fn render_p6(&self, name: &str) -> io::Result<()> {
+        let style = Style::default();
+        self.render_with_buffer(
+            name,
+            Format::P6,
+            &style,
+            |c| c.color(),
+            &mut Vec::new(),
+            &mut Vec::new(),
+        )
+    }
+
+    /// Output the current state of the canvas to a PNG file
+    #[cfg(feature = "image")]
+    pub fn render_png(&self, path: &str) -> io::Result<()> {
+        self.render_with(path, &Style::default(), |c| c.color())
+    }
+
+    /// Width, height and RGB pixels (3 bytes per pixel, line by line)
+    /// of the image drawn from the canvas
+    pub fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.to_rgb_buffer_with(&Style::default(), |c| c.color())
+    }
+
+    /// Same as `to_rgb_buffer`, but drawn according to `style` and with colors
+    /// chosen by `color` instead of the `Colorize` implementation
+    pub fn to_rgb_buffer_with<F: Fn(&T) -> Color>(
+        &self,
+        style: &Style,
+        color: F,
+    ) -> (usize, usize, Vec<u8>) {
+        let mut buf = Vec::new();
+        let (wth, hgt) = self.to_rgb_buffer_into(style, color, &mut buf);
+        (wth, hgt, buf)
+    }
+
+    /// Same as `to_rgb_buffer_with`, but the pixels are written to `buf`
+    /// (cleared first) and only the width and height are returned
+    pub fn to_rgb_buffer_into<F: Fn(&T) -> Color>(
+        &self,
+        style: &Style,
+        color: F,
+        buf: &mut Vec<u8>,
+    ) -> (usize, usize) {
+        let (sx, sy) = (style.scale_x.max(1), style.scale_y.max(1));
+        let (wth, hgt) = (self.wth * sx, self.hgt * sy);
+        let grid_lines = if sy > 1 { style.grid } else { None };
+        let grid_cols = if sx > 1 { style.grid } else { None };
+        buf.clear();
+        buf.reserve(wth * hgt * 3);
+        let correct = |(r, g, b): Color| match &style.lut {
+            Some(lut) => (lut[r as usize], lut[g as usize], lut[b as usize]),
+            None => (r, g, b),
+        };
+        let color = |c: &T| correct(color(c));
+        let (grid_lines, grid_cols) = (grid_lines.map(correct), grid_cols.map(correct));
+        for (i, line) in self.tab.iter().enumerate() {
+            let mut repeat = sy;
+            if let (Some((r, g, b)), true) = (grid_lines, i > 0) {
+                for _ in 0..wth {
+                    buf.extend_from_slice(&[r, g, b]);
+                }
+                repeat -= 1;
+            }
+            let start = buf.len();
+            for (j, g) in line.iter().enumerate() {
+                let (r, g, b) = color(g);
+                for dj in 0..sx {
+                    match grid_cols {
+                        Some((gr, gg, gb)) if dj == 0 && j > 0 => {
+                            buf.extend_from_slice(&[gr, gg, gb])
+                        }
+                        _ => buf.extend_from_slice(&[r, g, b]),
+                    }
+                }
+            }
+            for _ in 1..repeat {
+                buf.extend_from_within(start..start + wth * 3);
+            }
+        }
+        (wth, hgt)
+    }
+
+    /// Color of each cell, line by line, one entry per cell
+    pub fn color_grid(&self) -> Vec<Vec<Color>> {
+        self.color_grid_with(|c| c.color())
+    }
+
+    /// Same as `color_grid`, but with colors chosen by `color`
+    /// instead of the `Colorize` implementation
+    pub fn color_grid_with<F: Fn(&T) -> Color>(&self, color: F) -> Vec<Vec<Color>> {
+        self.tab
+            .iter()
+            .map(|line| line.iter().map(&color).collect())
+            .collect()
+    }
+
+    /// Width, height and RGB pixels of the image drawn from the canvas
+    /// as a hexagonal grid: each cell is a pointy-top hexagon of `radius`
+    /// pixels from center to corner, and odd lines are shifted half a cell
+    /// to the right (the layout of `Neighborhood::Hex`).
+    /// Pixels outside of all hexagons are filled with `background`.
+    pub fn render_hex(&self, radius: usize, background: Color) -> (usize, usize, Vec<u8>) {
+        self.render_hex_with(radius, background, |c| c.color())
+    }
+
+    /// Same as `render_hex`, but with colors chosen by `color`
+    /// instead of the `Colorize` implementation
+    pub fn render_hex_with<F: Fn(&T) -> Color>(
+        &self,
+        radius: usize,
+        background: Color,
+        color: F,
+    ) -> (usize, usize, Vec<u8>) {
+        let rad = radius.max(1) as f64;
+        // Horizontal distance between centers, vertical distance between lines
+        let (dx, dy) = (3f64.sqrt() * rad, 1.5 * rad);
+        let wth = (dx * (self.wth as f64 + 0.5)).ceil() as usize;
+        let hgt = if self.hgt == 0 {
+            0
+        } else {
+            (2.0 * rad + dy * (self.hgt - 1) as f64).ceil() as usize
+        };
+        let center = |i: isize, j: isize| {
+            let shift = if i.rem_euclid(2) == 1 { 1.0 } else { 0.5 };
+            ((j as f64 + shift) * dx, rad + i as f64 * dy)
+        };
+        let mut buf = Vec::with_capacity(wth * hgt * 3);
+        for py in 0..hgt {
+            let y = py as f64 + 0.5;
+            let i0 = (y / dy).floor() as isize;
+            for px in 0..wth {
+                let x = px as f64 + 0.5;
+                // The hexagons are the cells of the Voronoi diagram of their
+                // centers, so the pixel belongs to the closest center.
+                // Centers outside of the grid stand for the background.
+                let mut best = (f64::INFINITY, None);
+                for i in i0 - 1..=i0 + 1 {
+                    let j0 = (x / dx).floor() as isize;
+                    for j in j0 - 1..=j0 + 1 {
+                        let (cx, cy) = center(i, j);
+                        let d = (x - cx).powi(2) + (y - cy).powi(2);
+                        if d < best.0 {
+                            let inside =
+                                0 <= i && i < self.hgt as isize && 0 <= j && j < self.wth as isize;
+                            best = (
+                                d,
+                                if inside {
+                                    Some([i as usize, j as usize])
+                                } else {
+                                    None
+                                },
+                            );
+                        }
+                    }
+                }
+                let (r, g, b) = best.1.map_or(background, |idx| color(&self[idx]));
+                buf.extend_from_slice(&[r, g, b]);
+            }
+        }
+        (wth, hgt, buf)
+    }
+
+    /// Write the canvas to `out` as an SVG image of `cell_size` units
+    /// per cell: a rectangle of color `background` covers the whole image,
+    /// and cells get a `<rect>` of their own only if their color differs,
+    /// which keeps sparse boards small.
+    /// If `grid` is given, lines of that color are drawn between cells.
+    pub fn write_svg_with<W: Write, F: Fn(&T) -> Color>(
+        &self,
+        out: &mut W,
+        cell_size: f64,
+        background: Color,
+        grid: Option<Color>,
+        color: F,
+    ) -> io::Result<()> {
+        let hex = |(r, g, b): Color| format!("#{:02x}{:02x}{:02x}", r, g, b);
+        let (wth, hgt) = (self.wth as f64 * cell_size, self.hgt as f64 * cell_size);
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            wth, hgt
+        )?;
+        writeln!(
+            out,
+            r#"<rect width="{}" height="{}" fill="{}"/>"#,
+            wth,
+            hgt,
+            hex(background)
+        )?;
+        for (i, line) in self.tab.iter().enumerate() {
+            for (j, c) in line.iter().enumerate() {
+                let c = color(c);
+                if c != background {
+                    writeln!(
+                        out,
+                        r#"<rect x="{}" y="{}" width="{3}" height="{3}" fill="{}"/>"#,
+                        j as f64 * cell_size,
+                        i as f64 * cell_size,
+                        hex(c),
+                        cell_size
+                    )?;
+                }
+            }
+        }
+        if let Some(grid) = grid {
+            let mut d = String::new();
+            for i in 1..self.hgt {
+                d.push_str(&format!("M0 {}H{}", i as f64 * cell_size, wth));
+            }
+            for j in 1..self.wth {
+                d.push_str(&format!("M{} 0V{}", j as f64 * cell_size, hgt));
+            }
+            writeln!(
+                out,
+                r#"<path d="{}" stroke="{}" stroke-width="{}"/>"#,
+                d,
+                hex(grid),
+                cell_size / 10.
+            )?;
+        }
+        writeln!(out, "</svg>")
+    }
+
+    /// Text drawing of the canvas, one character per cell chosen by `glyph`
+    /// and one line of text (ending with `\n`) per line of cells
+    pub fn to_ascii_with<F: Fn(&T) -> char>(&self, glyph: F) -> String {
+        let mut res = String::with_capacity(self.hgt * (self.wth + 1));
         for line in &self.tab {
-            for g in line {
-                let (r, g, b) = g.color();
-                write!(f, "{} {} {} ", r, g, b).unwrap();
+            res.extend(line.iter().map(&glyph));
+            res.push('\n');
+        }
+        res
+    }
+
+    /// Text drawing of the canvas with two lines of cells per line of text,
+    /// for cells that are either on or off as decided by `on`:
+    /// each character is a space or one of the Unicode blocks
+    /// `▀`, `▄` and `█` depending on the cells above and below
+    pub fn to_half_blocks_with<F: Fn(&T) -> bool>(&self, on: F) -> String {
+        let mut res = String::with_capacity(self.hgt.div_ceil(2) * (3 * self.wth + 1));
+        for lines in self.tab.chunks(2) {
+            for j in 0..self.wth {
+                let below = lines.get(1).is_some_and(|line| on(&line[j]));
+                res.push(match (on(&lines[0][j]), below) {
+                    (false, false) => ' ',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (true, true) => '█',
+                });
             }
+            res.push('\n');
         }
-        f.flush().unwrap();
+        res
+    }
+
+    /// Same as `to_rgb_buffer_with`, but with 4 bytes per pixel:
+    /// an alpha channel set to 255 follows each RGB triple
+    pub fn to_rgba_buffer_with<F: Fn(&T) -> Color>(
+        &self,
+        style: &Style,
+        color: F,
+    ) -> (usize, usize, Vec<u8>) {
+        let (wth, hgt, rgb) = self.to_rgb_buffer_with(style, color);
+        let mut buf = Vec::with_capacity(wth * hgt * 4);
+        for px in rgb.chunks(3) {
+            buf.extend_from_slice(&[px[0], px[1], px[2], 255]);
+        }
+        (wth, hgt, buf)
     }
 
     /// Access array cells with wrapping
     pub fn mod_idx(&mut self, i: isize, j: isize) -> &mut T {
         &mut self.tab[mod_idx(i, self.hgt)][mod_idx(j, self.wth)]
     }
+
+    /// Index of the cell at offset `(mvi, mvj)` from `(i, j)`
+    /// (with wrapping around edges)
+    pub fn index_move(&self, i: usize, j: usize, mvi: isize, mvj: isize) -> [usize; 2] {
+        [
+            (i as isize + mvi).rem_euclid(self.hgt as isize) as usize,
+            (j as isize + mvj).rem_euclid(self.wth as isize) as usize,
+        ]
+    }
 }
 
 impl<T: Colorize> std::ops::Index<[usize; 2]> for Canvas<T> {
@@ -61,13 +520,240 @@ impl<T: Colorize> std::ops::IndexMut<[usize; 2]> for Canvas<T> {
     }
 }
 
+/// Append `v` in decimal followed by a space, much faster than `write!`
+fn push_decimal(out: &mut Vec<u8>, v: u8) {
+    if v >= 100 {
+        out.push(b'0' + v / 100);
+    }
+    if v >= 10 {
+        out.push(b'0' + v / 10 % 10);
+    }
+    out.extend_from_slice(&[b'0' + v % 10, b' ']);
+}
+
 /// Wrap i to the range [0, n)
 fn mod_idx(i: isize, n: usize) -> usize {
-    if i >= 0 {
-        (i % n as isize) as usize
-    } else {
-        let p = n / (-i as usize);
-        let i = (i as usize) + p * n;
-        i % n
+    i.rem_euclid(n as isize) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy)]
+    struct Px(Color);
+
+    impl Colorize for Px {
+        fn color(&self) -> Color {
+            self.0
+        }
+    }
+
+    fn temp(name: &str) -> String {
+        let dir = std::env::temp_dir().join("cellular_automata_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn p3_header_and_values() {
+        let colors = [
+            [(255, 255, 255), (0, 0, 0), (255, 0, 128)],
+            [(1, 2, 3), (0, 255, 0), (200, 100, 50)],
+        ];
+        let mut canvas = Canvas::new(2, 3, Px((0, 0, 0)));
+        for (i, line) in colors.iter().enumerate() {
+            for (j, &c) in line.iter().enumerate() {
+                canvas[[i, j]] = Px(c);
+            }
+        }
+        let file = temp("p3_header_and_values.ppm");
+        canvas.render(&file).unwrap();
+        let data = std::fs::read_to_string(&file).unwrap();
+        let tokens: Vec<_> = data.split_whitespace().collect();
+        // Width before height, then the maximum channel value
+        assert_eq!(tokens[..4], ["P3", "3", "2", "255"]);
+        let values: Vec<u8> = tokens[4..].iter().map(|v| v.parse().unwrap()).collect();
+        let expected: Vec<u8> = colors
+            .iter()
+            .flatten()
+            .flat_map(|&(r, g, b)| vec![r, g, b])
+            .collect();
+        assert_eq!(values, expected);
+
+        let style = Style {
+            scale_x: 2,
+            scale_y: 3,
+            ..Style::default()
+        };
+        canvas.render_with(&file, &style, |c| c.color()).unwrap();
+        let data = std::fs::read_to_string(&file).unwrap();
+        let tokens: Vec<_> = data.split_whitespace().collect();
+        assert_eq!(tokens[..4], ["P3", "6", "6", "255"]);
+        assert_eq!(tokens.len(), 4 + 6 * 6 * 3);
+    }
+
+    #[test]
+    fn hex_pixels_at_known_positions() {
+        let colors = [[(255, 0, 0), (0, 255, 0)], [(0, 0, 255), (255, 255, 0)]];
+        let mut canvas = Canvas::new(2, 2, Px((0, 0, 0)));
+        for (i, line) in colors.iter().enumerate() {
+            for (j, &c) in line.iter().enumerate() {
+                canvas[[i, j]] = Px(c);
+            }
+        }
+        let bg = (9, 9, 9);
+        let (wth, hgt, buf) = canvas.render_hex_with(10, bg, |c| c.color());
+        // 2.5 cells of sqrt(3) * 10 pixels, 2 radii plus 1.5 radii per extra line
+        assert_eq!((wth, hgt), (44, 35));
+        assert_eq!(buf.len(), wth * hgt * 3);
+        let px = |x: usize, y: usize| {
+            let k = 3 * (y * wth + x);
+            (buf[k], buf[k + 1], buf[k + 2])
+        };
+        // Centers of the hexagons, odd lines shifted half a cell to the right
+        assert_eq!(px(8, 10), colors[0][0]);
+        assert_eq!(px(25, 10), colors[0][1]);
+        assert_eq!(px(17, 25), colors[1][0]);
+        assert_eq!(px(34, 25), colors[1][1]);
+        // Left of the shifted line and outside of the pointy tops
+        assert_eq!(px(4, 10), colors[0][0]);
+        assert_eq!(px(4, 25), bg);
+        assert_eq!(px(0, 0), bg);
+        assert_eq!(px(43, 0), bg);
+        assert_eq!(px(0, 34), bg);
+    }
+
+    fn gradient_canvas(hgt: usize, wth: usize) -> Canvas<Px> {
+        let mut canvas = Canvas::new(hgt, wth, Px((0, 0, 0)));
+        for i in 0..hgt {
+            for j in 0..wth {
+                canvas[[i, j]] = Px(((i * 7) as u8, (j * 3) as u8, (i + j) as u8));
+            }
+        }
+        canvas
+    }
+
+    #[test]
+    fn reused_rgb_buffer_and_gamma() {
+        let canvas = gradient_canvas(20, 30);
+        let mut style = Style {
+            scale_x: 2,
+            grid: Some(GRID),
+            ..Style::default()
+        };
+        style.set_gamma(2.);
+        let mut buf = vec![1; 7];
+        for _ in 0..2 {
+            let dims = canvas.to_rgb_buffer_into(&style, |c| c.color(), &mut buf);
+            let (wth, hgt, fresh) = canvas.to_rgb_buffer_with(&style, |c| c.color());
+            assert_eq!(dims, (wth, hgt));
+            assert_eq!(buf, fresh);
+        }
+        let mut one = Canvas::new(1, 3, Px((0, 64, 255)));
+        one[[0, 1]] = Px((128, 1, 200));
+        let mut style = Style::default();
+        style.set_gamma(2.);
+        let (_, _, buf) = one.to_rgb_buffer_with(&style, |c| c.color());
+        assert_eq!(buf, [0, 128, 255, 181, 16, 226, 0, 128, 255]);
+        style.set_gamma(1.);
+        let (_, _, buf) = one.to_rgb_buffer_with(&style, |c| c.color());
+        assert_eq!(buf, [0, 64, 255, 128, 1, 200, 0, 64, 255]);
+    }
+
+    #[test]
+    fn p6_uses_the_style() {
+        let canvas = gradient_canvas(3, 4);
+        let mut style = Style {
+            scale_y: 2,
+            ..Style::default()
+        };
+        style.set_gamma(1.5);
+        let file = temp("p6_uses_the_style.ppm");
+        let (mut rgb, mut out) = (Vec::new(), Vec::new());
+        canvas
+            .render_with_buffer(&file, Format::P6, &style, |c| c.color(), &mut rgb, &mut out)
+            .unwrap();
+        let data = std::fs::read(&file).unwrap();
+        let header = b"P6\n4 6\n255\n";
+        assert_eq!(&data[..header.len()], header);
+        let (_, _, pixels) = canvas.to_rgb_buffer_with(&style, |c| c.color());
+        assert_eq!(data[header.len()..], pixels[..]);
+    }
+
+    /// `cargo test --release ppm_formats_benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn ppm_formats_benchmark() {
+        let canvas = gradient_canvas(500, 800);
+        let style = Style::default();
+        let file = temp("ppm_formats_benchmark.ppm");
+        let (mut rgb, mut out) = (Vec::new(), Vec::new());
+        for &format in &[Format::Ppm, Format::P6] {
+            let start = std::time::Instant::now();
+            for _ in 0..10 {
+                canvas
+                    .render_with_buffer(&file, format, &style, |c| c.color(), &mut rgb, &mut out)
+                    .unwrap();
+            }
+            let size = std::fs::metadata(&file).unwrap().len();
+            println!(
+                "800x500, {:?}: {:?} per frame, {} bytes",
+                format,
+                start.elapsed() / 10,
+                size
+            );
+        }
+    }
+
+    /// `cargo test --release frame_buffers_benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn frame_buffers_benchmark() {
+        let canvas = gradient_canvas(500, 800);
+        let mut style = Style::default();
+        style.set_gamma(2.2);
+        let file = temp("frame_buffers_benchmark.ppm");
+        let start = std::time::Instant::now();
+        for _ in 0..10 {
+            canvas.render_with(&file, &style, |c| c.color()).unwrap();
+        }
+        println!("800x500, new buffers: {:?} per frame", start.elapsed() / 10);
+        let (mut rgb, mut out) = (Vec::new(), Vec::new());
+        let start = std::time::Instant::now();
+        for _ in 0..10 {
+            canvas
+                .render_with_buffer(
+                    &file,
+                    Format::Ppm,
+                    &style,
+                    |c| c.color(),
+                    &mut rgb,
+                    &mut out,
+                )
+                .unwrap();
+        }
+        println!(
+            "800x500, reused buffers: {:?} per frame",
+            start.elapsed() / 10
+        );
+    }
+
+    #[test]
+    fn mod_idx_is_python_modulo() {
+        for n in 1..=12 {
+            let m = n as isize;
+            for i in -3 * m..3 * m {
+                // Python's `i % n` for a positive `n`, without `rem_euclid`
+                let mut expected = i;
+                while expected < 0 {
+                    expected += m;
+                }
+                while expected >= m {
+                    expected -= m;
+                }
+                assert_eq!(mod_idx(i, n), expected as usize, "{} mod {}", i, n);
+            }
+        }
     }
 }
@@ -0,0 +1,235 @@
+use rand::Rng;
+use std::io;
+
+use crate::automaton::Automaton;
+use crate::canvas::*;
+use crate::lifelike::Rules;
+
+/// Colors of the first teams, cycled through when there are more teams
+pub const TEAM_COLORS: [Color; 8] = [
+    (255, 50, 50),
+    (50, 100, 255),
+    (50, 220, 50),
+    (255, 230, 50),
+    (230, 50, 230),
+    (50, 230, 230),
+    (255, 150, 30),
+    (150, 80, 255),
+];
+
+/// A cell of a colored automaton is either dead or alive and belongs to a team
+#[derive(Clone, Copy)]
+struct Cell {
+    team: Option<u8>,
+    /// Changing the state has to be done after all cells have been checked
+    succ: Option<u8>,
+}
+
+impl Colorize for Cell {
+    fn color(&self) -> Color {
+        match self.team {
+            None => (0, 0, 0),
+            Some(t) => TEAM_COLORS[t as usize % TEAM_COLORS.len()],
+        }
+    }
+}
+
+/// A life-like automaton whose live cells belong to one of several teams
+/// (as in Immigration or QuadLife): births and deaths follow the usual rules,
+/// and a newborn cell joins the team most represented among its live neighbors.
+pub struct ColoredLife {
+    rules: Rules,
+    teams: u8,
+    field: Canvas<Cell>,
+    hgt: usize,
+    wth: usize,
+    cnt: usize,
+    born: usize,
+}
+
+impl ColoredLife {
+    /// Rules are given at the initialization, and cannot be modified.
+    /// There should be at least one team.
+    pub fn new(hgt: usize, wth: usize, rules: &str, teams: u8) -> Self {
+        assert!(teams > 0, "there should be at least one team");
+        Self {
+            rules: Rules::new(rules),
+            teams,
+            field: Canvas::new(hgt, wth, Cell::new()),
+            hgt,
+            wth,
+            cnt: 0,
+            born: 0,
+        }
+    }
+
+    /// Birth cells at random all over the canvas.
+    /// p is the probability for any cell of being born, and newborn
+    /// cells join team `t` with a probability proportional to `weights[t]`.
+    pub fn init_rand(&mut self, p: f64, weights: &[f64]) {
+        self.init_cluster(1., p, weights);
+    }
+
+    /// Birth cells at random with probability p, but only within an area
+    /// around the center of size given by f
+    /// (proportion of the total dimensions).
+    /// Teams are chosen according to `weights` as in `init_rand`.
+    pub fn init_cluster(&mut self, f: f64, p: f64, weights: &[f64]) {
+        assert_eq!(
+            weights.len(),
+            self.teams as usize,
+            "there should be one weight per team"
+        );
+        let total: f64 = weights.iter().sum();
+        let mut rng = rand::thread_rng();
+        let lo = |n| (n as f64 * (1. - f) / 2.).floor() as usize;
+        let hi = |n| (n as f64 * (1. + f) / 2.).floor() as usize;
+        for i in lo(self.hgt)..hi(self.hgt) {
+            for j in lo(self.wth)..hi(self.wth) {
+                if rng.gen::<f64>() < p {
+                    let mut x = rng.gen::<f64>() * total;
+                    let mut team = self.teams - 1;
+                    for (t, w) in weights.iter().enumerate() {
+                        if x < *w {
+                            team = t as u8;
+                            break;
+                        }
+                        x -= w;
+                    }
+                    self.field[[i, j]].succ = Some(team);
+                }
+            }
+        }
+        self.update();
+    }
+
+    /// Set a single cell to be alive in `team`, or dead with `None`
+    pub fn set(&mut self, i: usize, j: usize, team: Option<u8>) {
+        self.field[[i % self.hgt, j % self.wth]].succ = team.map(|t| t % self.teams);
+        self.update();
+    }
+
+    /// Set each cell to its next state and count the newborn cells
+    pub fn update(&mut self) {
+        self.born = 0;
+        self.cnt = 0;
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                let cell = &mut self.field[[i, j]];
+                if cell.succ.is_some() && cell.team.is_none() {
+                    self.born += 1;
+                }
+                cell.update();
+                if cell.is_alive() {
+                    self.cnt += 1;
+                }
+            }
+        }
+    }
+
+    /// Number of live cells of each team
+    pub fn populations(&self) -> Vec<usize> {
+        let mut res = vec![0; self.teams as usize];
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                if let Some(t) = self.field[[i, j]].team {
+                    res[t as usize] += 1;
+                }
+            }
+        }
+        res
+    }
+
+    /// Count live neighbors of each team (Moore neighborhood)
+    fn count_neigh(&self, i: usize, j: usize, counts: &mut [usize]) {
+        counts.iter_mut().for_each(|c| *c = 0);
+        for mvi in -1..=1 {
+            for mvj in -1..=1 {
+                if (mvi, mvj) != (0, 0) {
+                    if let Some(t) = self.field[self.field.index_move(i, j, mvi, mvj)].team {
+                        counts[t as usize] += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Calculate next state of the automaton.
+    /// Ties between the teams of the neighbors of a newborn cell
+    /// are won by the team with the smallest index.
+    pub fn next(&mut self) {
+        let mut counts = vec![0; self.teams as usize];
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                self.count_neigh(i, j, &mut counts);
+                let neigh = counts.iter().sum();
+                let cell = &mut self.field[[i, j]];
+                cell.succ = match cell.team {
+                    None if self.rules.born(neigh) => {
+                        let mut best = 0;
+                        for (t, &c) in counts.iter().enumerate() {
+                            if c > counts[best] {
+                                best = t;
+                            }
+                        }
+                        Some(best as u8)
+                    }
+                    Some(t) if self.rules.survives(neigh) => Some(t),
+                    _ => None,
+                };
+            }
+        }
+        self.update();
+    }
+
+    /// Output current state to a file
+    pub fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        let name = cfg.render(&self.field)?;
+
+        eprint!(
+            "\rDone generation {} : {} alive (+{}) {:?}",
+            name,
+            self.cnt,
+            self.born,
+            self.populations()
+        );
+        Ok(())
+    }
+}
+
+impl Automaton for ColoredLife {
+    fn next(&mut self) {
+        ColoredLife::next(self);
+    }
+
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        ColoredLife::render(self, cfg)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        self.field.dimensions()
+    }
+
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.field.to_rgb_buffer()
+    }
+}
+
+impl Cell {
+    /// All cells are created dead by default.
+    pub fn new() -> Self {
+        Self {
+            team: None,
+            succ: None,
+        }
+    }
+
+    /// Set current state to calculated next state
+    pub fn update(&mut self) {
+        self.team = self.succ;
+    }
+
+    pub fn is_alive(self) -> bool {
+        self.team.is_some()
+    }
+}
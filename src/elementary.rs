@@ -0,0 +1,119 @@
+use rand::Rng;
+use std::io;
+
+use crate::automaton::Automaton;
+use crate::canvas::*;
+
+/// A cell of an elementary automaton is either alive or dead
+#[derive(Clone, Copy)]
+struct Cell {
+    alive: bool,
+}
+
+impl Colorize for Cell {
+    fn color(&self) -> Color {
+        if self.alive {
+            (255, 255, 255)
+        } else {
+            (0, 0, 0)
+        }
+    }
+}
+
+/// An elementary (one-dimensional) cellular automaton.
+/// Each generation is a line of the canvas, so that the whole
+/// evolution is rendered as a single space-time diagram.
+pub struct Elementary {
+    rule: u8,
+    field: Canvas<Cell>,
+    hgt: usize,
+    wth: usize,
+    gen: usize,
+}
+
+impl Elementary {
+    /// The rule is given by its Wolfram code: bit `4l + 2c + r` of `rule`
+    /// is the next state of a cell whose left neighbor, self and right
+    /// neighbor are `l`, `c` and `r`.
+    /// `gens` is the number of generations (lines) that will be computed.
+    pub fn new(rule: u8, wth: usize, gens: usize) -> Self {
+        Self {
+            rule,
+            field: Canvas::new(gens, wth, Cell { alive: false }),
+            hgt: gens,
+            wth,
+            gen: 0,
+        }
+    }
+
+    /// Start from a single live cell in the middle of the first line
+    pub fn init_single(&mut self) {
+        self.field[[0, self.wth / 2]].alive = true;
+    }
+
+    /// Birth cells at random on the first line.
+    /// p is the probability for any cell of being born.
+    pub fn init_rand(&mut self, p: f64) {
+        let mut rng = rand::thread_rng();
+        for j in 0..self.wth {
+            self.field[[0, j]].alive = rng.gen::<f64>() < p;
+        }
+    }
+
+    /// Calculate the next line (with wrapping around edges).
+    /// Does nothing once all generations have been computed.
+    pub fn next(&mut self) {
+        if self.gen + 1 >= self.hgt {
+            return;
+        }
+        for j in 0..self.wth {
+            let mut idx = 0;
+            for mvj in -1..=1 {
+                idx <<= 1;
+                if self.field[self.field.index_move(self.gen, j, 0, mvj)].alive {
+                    idx += 1;
+                }
+            }
+            self.field[[self.gen + 1, j]].alive = (self.rule >> idx) & 1 == 1;
+        }
+        self.gen += 1;
+    }
+
+    /// Calculate all remaining generations
+    pub fn run(&mut self) {
+        while self.gen + 1 < self.hgt {
+            self.next();
+        }
+    }
+
+    /// Output the diagram so far to a file
+    pub fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        let name = cfg.render(&self.field)?;
+
+        eprint!("\rDone frame {} : generation {}", name, self.gen);
+        Ok(())
+    }
+}
+
+impl Automaton for Elementary {
+    fn next(&mut self) {
+        Elementary::next(self);
+    }
+
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        Elementary::render(self, cfg)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        self.field.dimensions()
+    }
+
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.field.to_rgb_buffer()
+    }
+}
+
+pub const RULE_30: u8 = 30;
+pub const RULE_90: u8 = 90;
+pub const RULE_110: u8 = 110;
+pub const RULE_184: u8 = 184;
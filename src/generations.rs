@@ -0,0 +1,262 @@
+use rand::Rng;
+use std::io;
+
+use crate::automaton::Automaton;
+use crate::canvas::*;
+use crate::lifelike::{RuleParseError, Rules};
+
+/// A cell of a Generations automaton is either dead (0), alive (1),
+/// or dying (2 and above), in which case it ages by one every generation
+/// until it reaches the number of states and dies.
+#[derive(Clone, Copy)]
+struct Cell {
+    curr: usize,
+    /// Changing the state has to be done after all cells have been checked
+    succ: usize,
+    /// Total number of states, required to choose the fading color
+    states: usize,
+}
+
+impl Colorize for Cell {
+    fn color(&self) -> Color {
+        match self.curr {
+            0 => (0, 0, 0),
+            1 => (255, 255, 255),
+            k => {
+                let v = 255 * (self.states - k) / (self.states - 1);
+                (0, 0, v as u8)
+            }
+        }
+    }
+}
+
+/// A Generations automaton behaves like a life-like one, except that
+/// cells that fail to survive go through a number of dying states
+/// during which they neither count as alive nor can be born.
+pub struct Generations {
+    rules: Rules,
+    states: usize,
+    field: Canvas<Cell>,
+    hgt: usize,
+    wth: usize,
+    cnt: usize,
+    born: usize,
+}
+
+impl Generations {
+    /// Rules are given at the initialization, and cannot be modified.
+    /// See `parse` for the notation.
+    pub fn new(hgt: usize, wth: usize, rules: &str) -> Self {
+        let (rules, states) = Self::parse(rules).unwrap_or_else(|e| panic!("{}", e));
+        Self {
+            rules,
+            states,
+            field: Canvas::new(hgt, wth, Cell::new(states)),
+            hgt,
+            wth,
+            cnt: 0,
+            born: 0,
+        }
+    }
+
+    /// Generations rules are written either `S/B/C` (e.g. `345/2/4`) as in
+    /// Golly, or with explicit sections `B2/S345/C4` in any order.
+    /// `C` is the total number of states, including alive and dead:
+    /// there are `C - 2` dying states.
+    pub fn parse(s: &str) -> Result<(Rules, usize), RuleParseError> {
+        let s = s.trim();
+        let v: Vec<_> = s.split('/').collect();
+        if v.len() != 3 {
+            return Err(RuleParseError::MissingSection(s.to_string()));
+        }
+        let tagged = v
+            .iter()
+            .all(|part| part.starts_with(|c: char| c.is_ascii_alphabetic()));
+        let (b, sv, c) = if tagged {
+            let (mut b, mut sv, mut c) = (None, None, None);
+            for part in &v {
+                let rest = &part[1..];
+                match part.chars().next() {
+                    Some('B') | Some('b') => b = Some(rest),
+                    Some('S') | Some('s') => sv = Some(rest),
+                    Some('C') | Some('c') | Some('G') | Some('g') => c = Some(rest),
+                    _ => return Err(RuleParseError::MissingSection(s.to_string())),
+                }
+            }
+            match (b, sv, c) {
+                (Some(b), Some(sv), Some(c)) => (b, sv, c),
+                _ => return Err(RuleParseError::MissingSection(s.to_string())),
+            }
+        } else {
+            (v[1], v[0], v[2])
+        };
+        let rules = Rules::try_new(&format!("B{}/S{}", b, sv))?;
+        match c.parse::<usize>() {
+            Ok(states) if states >= 2 => Ok((rules, states)),
+            _ => Err(RuleParseError::InvalidStates(c.to_string())),
+        }
+    }
+
+    /// Birth cells at random all over the canvas.
+    /// p is the probability for any cell of being born.
+    pub fn init_rand(&mut self, p: f64) {
+        let mut rng = rand::thread_rng();
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                if rng.gen::<f64>() < p {
+                    self.field[[i, j]].birth();
+                }
+            }
+        }
+        self.update();
+    }
+
+    /// Birth cells at random with probability p, but only within an area
+    /// around the center of size given by f
+    /// (proportion of the total dimensions)
+    pub fn init_cluster(&mut self, f: f64, p: f64) {
+        let mut rng = rand::thread_rng();
+        let lo = |n| (n as f64 * (1. - f) / 2.).floor() as usize;
+        let hi = |n| (n as f64 * (1. + f) / 2.).floor() as usize;
+        for i in lo(self.hgt)..hi(self.hgt) {
+            for j in lo(self.wth)..hi(self.wth) {
+                if rng.gen::<f64>() < p {
+                    self.field[[i, j]].birth();
+                }
+            }
+        }
+        self.update();
+    }
+
+    /// Set each cell to its next state and count the newborn cells
+    pub fn update(&mut self) {
+        self.born = 0;
+        self.cnt = 0;
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                let cell = &mut self.field[[i, j]];
+                if cell.succ == 1 && cell.curr != 1 {
+                    self.born += 1;
+                }
+                cell.update();
+                if cell.is_alive() {
+                    self.cnt += 1;
+                }
+            }
+        }
+    }
+
+    /// Count live neighbors (Moore neighborhood), dying cells are ignored
+    fn count_neigh(&self, i: usize, j: usize) -> usize {
+        let mut res = 0;
+        for mvi in -1..=1 {
+            for mvj in -1..=1 {
+                if (mvi, mvj) != (0, 0)
+                    && self.field[self.field.index_move(i, j, mvi, mvj)].is_alive()
+                {
+                    res += 1;
+                }
+            }
+        }
+        res
+    }
+
+    /// Calculate next state of the automaton
+    pub fn next(&mut self) {
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                let neigh = self.count_neigh(i, j);
+                let cell = &mut self.field[[i, j]];
+                match cell.curr {
+                    0 => {
+                        if self.rules.born(neigh) {
+                            cell.birth();
+                        }
+                    }
+                    1 => {
+                        if !self.rules.survives(neigh) {
+                            cell.age();
+                        }
+                    }
+                    _ => cell.age(),
+                }
+            }
+        }
+        self.update();
+    }
+
+    /// Current state as text: `#` for live cells, spaces for dead ones,
+    /// and the number of generations since they stopped surviving
+    /// for dying cells (in base 36, `+` beyond)
+    pub fn render_ascii(&self) -> String {
+        self.field.to_ascii_with(|c| match c.curr {
+            0 => ' ',
+            1 => '#',
+            k => std::char::from_digit((k - 1) as u32, 36).unwrap_or('+'),
+        })
+    }
+
+    /// Output current state to a file
+    pub fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        let name = cfg.render(&self.field)?;
+
+        eprint!(
+            "\rDone generation {} : {} alive (+{}) out of {} states",
+            name, self.cnt, self.born, self.states
+        );
+        Ok(())
+    }
+}
+
+impl Automaton for Generations {
+    fn next(&mut self) {
+        Generations::next(self);
+    }
+
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        Generations::render(self, cfg)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        self.field.dimensions()
+    }
+
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.field.to_rgb_buffer()
+    }
+}
+
+impl Cell {
+    /// All cells are created dead by default.
+    pub fn new(states: usize) -> Self {
+        Self {
+            curr: 0,
+            succ: 0,
+            states,
+        }
+    }
+
+    pub fn birth(&mut self) {
+        self.succ = 1;
+    }
+
+    /// Move one step closer to death
+    pub fn age(&mut self) {
+        self.succ = (self.curr + 1) % self.states;
+    }
+
+    /// Set current state to calculated next state
+    pub fn update(&mut self) {
+        self.curr = self.succ;
+    }
+
+    pub fn is_alive(self) -> bool {
+        self.curr == 1
+    }
+}
+
+pub const STAR_WARS: &str = "345/2/4";
+pub const BRIANS_BRAIN: &str = "/2/3";
+pub const BLOOMERANG: &str = "234/34678/24";
+pub const FIREWORKS: &str = "2/13/21";
+pub const LIVING_ON_THE_EDGE: &str = "345/3/6";
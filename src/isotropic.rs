@@ -0,0 +1,395 @@
+use rand::Rng;
+use std::io;
+
+use crate::automaton::Automaton;
+use crate::canvas::*;
+use crate::lifelike::RuleParseError;
+
+/// Positions of the neighbors, neighbor `k` is bit `k` of a configuration
+const POSITIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// Letters of the Hensel notation for 0 to 4 live neighbors
+/// (5 to 8 use the same letters as their complement)
+const LETTERS: [&str; 5] = ["", "ce", "ceaikn", "ceaiknjqry", "ceaiknjqrytwz"];
+
+/// One configuration for each letter of `LETTERS`, as in Golly:
+/// bits 0 to 8 are the 3x3 block in reading order, 4 is the cell itself
+const REPRESENTATIVES: [&[u16]; 5] = [
+    &[],
+    &[1, 2],
+    &[5, 10, 3, 40, 33, 68],
+    &[69, 42, 11, 7, 98, 13, 14, 70, 41, 97],
+    &[325, 170, 15, 45, 99, 71, 106, 102, 43, 101, 105, 78, 108],
+];
+
+/// Convert a 3x3 block without its center to a configuration of neighbors
+fn from_block(block: u16) -> u8 {
+    let low = block & 0b1111;
+    let high = (block >> 5) & 0b1111;
+    (low | high << 4) as u8
+}
+
+/// All configurations equivalent to `conf` up to rotation and reflection
+fn orbit(conf: u8) -> Vec<u8> {
+    let cells: Vec<_> = (0..8)
+        .filter(|k| conf >> k & 1 == 1)
+        .map(|k| POSITIONS[k])
+        .collect();
+    let mut res = Vec::with_capacity(8);
+    for sym in 0..8 {
+        let mut c = 0;
+        for &(i, j) in &cells {
+            let (i, j) = if sym & 4 != 0 { (i, -j) } else { (i, j) };
+            let (i, j) = match sym & 3 {
+                0 => (i, j),
+                1 => (j, -i),
+                2 => (-i, -j),
+                _ => (-j, i),
+            };
+            let k = POSITIONS.iter().position(|&p| p == (i, j)).unwrap();
+            c |= 1 << k;
+        }
+        if !res.contains(&c) {
+            res.push(c);
+        }
+    }
+    res
+}
+
+/// Configurations of `n` live neighbors designated by `letter`
+fn class(n: usize, letter: char) -> Option<Vec<u8>> {
+    let m = n.min(8 - n);
+    let k = LETTERS[m].find(letter)?;
+    let conf = from_block(REPRESENTATIVES[m][k]);
+    Some(orbit(if n > 4 { !conf } else { conf }))
+}
+
+/// Isotropic non-totalistic rules: birth and survival depend on the
+/// arrangement of the 8 neighbors (up to rotation and reflection),
+/// not only on their number
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct IsotropicRules {
+    /// Indexed by the configuration of the neighbors
+    b: [bool; 256],
+    s: [bool; 256],
+}
+
+impl IsotropicRules {
+    /// Parse rules in Hensel notation, e.g. `B2-a/S12` or `B3/S2-i34q`:
+    /// each neighbor count may be followed by letters to only allow these
+    /// configurations, or by `-` and letters to allow all others.
+    /// A count alone allows all configurations, so totalistic rules
+    /// are also accepted.
+    pub fn new(s: &str) -> Result<Self, RuleParseError> {
+        let mut r = IsotropicRules {
+            b: [false; 256],
+            s: [false; 256],
+        };
+        let s = s.trim();
+        let v: Vec<_> = s.split('/').collect();
+        if v.len() != 2 {
+            return Err(RuleParseError::MissingSection(s.to_string()));
+        }
+        let mut seen = [false; 2];
+        for part in v {
+            let mut it = part.chars();
+            let (tab, idx) = match it.next() {
+                Some('B') | Some('b') => (&mut r.b, 0),
+                Some('S') | Some('s') => (&mut r.s, 1),
+                _ => return Err(RuleParseError::MissingSection(s.to_string())),
+            };
+            seen[idx] = true;
+            Self::read_section(tab, it.as_str())?;
+        }
+        if seen != [true, true] {
+            return Err(RuleParseError::MissingSection(s.to_string()));
+        }
+        Ok(r)
+    }
+
+    /// Allow in `tab` the configurations listed in `section`
+    fn read_section(tab: &mut [bool; 256], section: &str) -> Result<(), RuleParseError> {
+        let mut chars = section.chars().peekable();
+        while let Some(c) = chars.next() {
+            let n = match c.to_digit(10) {
+                Some(n) if n <= 8 => n as usize,
+                _ => return Err(RuleParseError::InvalidChar(c)),
+            };
+            let negate = chars.peek() == Some(&'-');
+            if negate {
+                chars.next();
+            }
+            let mut letters = Vec::new();
+            while let Some(&l) = chars.peek().filter(|l| l.is_ascii_lowercase()) {
+                letters.push(l);
+                chars.next();
+            }
+            let all = LETTERS[n.min(8 - n)];
+            if let Some(&l) = letters.iter().find(|&&l| !all.contains(l)) {
+                return Err(RuleParseError::InvalidChar(l));
+            }
+            if all.is_empty() {
+                // A single configuration without a letter
+                tab[if n == 0 { 0 } else { 0xff }] = true;
+            }
+            for l in all.chars() {
+                if letters.is_empty() || letters.contains(&l) != negate {
+                    for conf in class(n, l).unwrap() {
+                        tab[conf as usize] = true;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a dead cell whose neighbors are `conf` is born
+    /// (bit `k` of `conf` is the `k`-th neighbor in reading order)
+    pub fn born(&self, conf: u8) -> bool {
+        self.b[conf as usize]
+    }
+
+    /// Whether a live cell whose neighbors are `conf` survives
+    pub fn survives(&self, conf: u8) -> bool {
+        self.s[conf as usize]
+    }
+}
+
+/// A cell of an isotropic automaton is either dead or alive
+#[derive(Clone, Copy)]
+struct Cell {
+    alive: bool,
+    /// Changing the state has to be done after all cells have been checked
+    succ: bool,
+}
+
+impl Colorize for Cell {
+    fn color(&self) -> Color {
+        if self.alive {
+            (255, 255, 255)
+        } else {
+            (0, 0, 0)
+        }
+    }
+}
+
+/// An automaton with isotropic non-totalistic rules
+/// on the Moore neighborhood, wrapping around the edges
+pub struct Isotropic {
+    rules: IsotropicRules,
+    field: Canvas<Cell>,
+    hgt: usize,
+    wth: usize,
+    cnt: usize,
+    born: usize,
+}
+
+impl Isotropic {
+    /// Rules are given at the initialization, and cannot be modified.
+    /// See `IsotropicRules::new` for the notation.
+    pub fn new(hgt: usize, wth: usize, rules: &str) -> Self {
+        Self {
+            rules: IsotropicRules::new(rules).unwrap_or_else(|e| panic!("{}", e)),
+            field: Canvas::new(
+                hgt,
+                wth,
+                Cell {
+                    alive: false,
+                    succ: false,
+                },
+            ),
+            hgt,
+            wth,
+            cnt: 0,
+            born: 0,
+        }
+    }
+
+    /// Birth cells at random all over the canvas.
+    /// p is the probability for any cell of being born.
+    pub fn init_rand(&mut self, p: f64) {
+        self.init_cluster(1., p);
+    }
+
+    /// Birth cells at random with probability p, but only within an area
+    /// around the center of size given by f
+    /// (proportion of the total dimensions)
+    pub fn init_cluster(&mut self, f: f64, p: f64) {
+        let mut rng = rand::thread_rng();
+        let lo = |n| (n as f64 * (1. - f) / 2.).floor() as usize;
+        let hi = |n| (n as f64 * (1. + f) / 2.).floor() as usize;
+        for i in lo(self.hgt)..hi(self.hgt) {
+            for j in lo(self.wth)..hi(self.wth) {
+                if rng.gen::<f64>() < p {
+                    self.field[[i, j]].succ = true;
+                }
+            }
+        }
+        self.update();
+    }
+
+    /// Set a single cell to be alive or dead
+    pub fn set(&mut self, i: usize, j: usize, alive: bool) {
+        self.field[[i % self.hgt, j % self.wth]].succ = alive;
+        self.update();
+    }
+
+    /// Whether the cell at `(i, j)` is alive
+    pub fn get(&self, i: usize, j: usize) -> bool {
+        self.field[[i % self.hgt, j % self.wth]].alive
+    }
+
+    /// Set each cell to its next state and count the newborn cells
+    pub fn update(&mut self) {
+        self.born = 0;
+        self.cnt = 0;
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                let cell = &mut self.field[[i, j]];
+                if cell.succ && !cell.alive {
+                    self.born += 1;
+                }
+                cell.alive = cell.succ;
+                if cell.alive {
+                    self.cnt += 1;
+                }
+            }
+        }
+    }
+
+    /// Configuration of the neighbors of a cell
+    fn neighbors(&self, i: usize, j: usize) -> u8 {
+        let mut conf = 0;
+        for (k, &(mvi, mvj)) in POSITIONS.iter().enumerate() {
+            if self.field[self.field.index_move(i, j, mvi, mvj)].alive {
+                conf |= 1 << k;
+            }
+        }
+        conf
+    }
+
+    /// Calculate next state of the automaton
+    pub fn next(&mut self) {
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                let conf = self.neighbors(i, j);
+                let cell = &mut self.field[[i, j]];
+                cell.succ = if cell.alive {
+                    self.rules.survives(conf)
+                } else {
+                    self.rules.born(conf)
+                };
+            }
+        }
+        self.update();
+    }
+
+    /// Output current state to a file
+    pub fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        let name = cfg.render(&self.field)?;
+
+        eprint!(
+            "\rDone generation {} : {} alive (+{})",
+            name, self.cnt, self.born
+        );
+        Ok(())
+    }
+}
+
+impl Automaton for Isotropic {
+    fn next(&mut self) {
+        Isotropic::next(self);
+    }
+
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        Isotropic::render(self, cfg)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        self.field.dimensions()
+    }
+
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.field.to_rgb_buffer()
+    }
+}
+
+pub const TLIFE: &str = "B3/S2-i34q";
+pub const JUST_FRIENDS: &str = "B2-a/S12";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Live cells of a board after `gens` generations from `cells`
+    fn run(rules: &str, cells: &[(usize, usize)], gens: usize) -> Vec<(usize, usize)> {
+        let mut game = Isotropic::new(8, 8, rules);
+        for &(i, j) in cells {
+            game.set(i, j, true);
+        }
+        for _ in 0..gens {
+            game.next();
+        }
+        (0..8)
+            .flat_map(|i| (0..8).map(move |j| (i, j)))
+            .filter(|&(i, j)| game.get(i, j))
+            .collect()
+    }
+
+    #[test]
+    fn classes_partition_configurations() {
+        let mut seen = [0; 256];
+        for n in 0..=8 {
+            let letters = LETTERS[n.min(8 - n)];
+            let classes: Vec<Vec<u8>> = if letters.is_empty() {
+                vec![vec![if n == 0 { 0 } else { 0xff }]]
+            } else {
+                letters.chars().map(|l| class(n, l).unwrap()).collect()
+            };
+            for conf in classes.into_iter().flatten() {
+                assert_eq!(conf.count_ones() as usize, n, "{:#010b}", conf);
+                seen[conf as usize] += 1;
+            }
+        }
+        assert!(seen.iter().all(|&k| k == 1), "{:?}", seen);
+    }
+
+    #[test]
+    fn totalistic_rules_are_life() {
+        let rules = IsotropicRules::new("B3/S23").unwrap();
+        for conf in 0..=255u8 {
+            let n = conf.count_ones();
+            assert_eq!(rules.born(conf), n == 3, "{:#010b}", conf);
+            assert_eq!(rules.survives(conf), n == 2 || n == 3, "{:#010b}", conf);
+        }
+    }
+
+    #[test]
+    fn tlife_and_just_friends() {
+        // Neighbors by bit: NW, N, NE, W, E, SW, S, SE
+        let (n, s, nw) = (1 << 1, 1 << 6, 1 << 0);
+        let tlife = IsotropicRules::new(TLIFE).unwrap();
+        assert!(!tlife.survives(n | s));
+        assert!(tlife.survives(n | nw));
+        let friends = IsotropicRules::new(JUST_FRIENDS).unwrap();
+        assert!(!friends.born(n | nw));
+        assert!(friends.born(n | s));
+        // The beehive is still, but the middle of a blinker has 2i neighbors
+        let beehive = [(2, 3), (2, 4), (3, 2), (3, 5), (4, 3), (4, 4)];
+        assert_eq!(run(TLIFE, &beehive, 3), beehive);
+        assert_eq!(run(TLIFE, &[(3, 2), (3, 3), (3, 4)], 1), [(2, 3), (4, 3)]);
+        assert_eq!(
+            run("B3/S23", &[(3, 2), (3, 3), (3, 4)], 1),
+            [(2, 3), (3, 3), (4, 3)]
+        );
+    }
+}
@@ -1,76 +1,390 @@
-use rand::Rng;
-use std::ascii;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
 
+use crate::automaton::Automaton;
 use crate::canvas::*;
+use crate::sparse::SparseLife;
+use crate::stats::StatsLogger;
 
-/// A cell in a life-like automata can only be alive or dead
+/// A cell in a life-like automata can only be alive or dead.
+/// The board is stored as bits, cells are only built to draw it.
 #[derive(Clone, Copy)]
 struct Cell {
-    curr: bool,
-    succ: bool,
+    alive: bool,
+    /// Number of generations the cell has been alive for
+    age: usize,
+    /// Number of times the cell was born or died
+    activity: usize,
 }
 
 impl Colorize for Cell {
     fn color(&self) -> Color {
-        if self.curr {
-            (25, 25, 25)
+        if self.alive {
+            ALIVE
         } else {
-            (0, 0, 0)
+            DEAD
         }
     }
 }
 
+/// Default color of live cells
+pub const ALIVE: Color = (0, 255, 0);
+/// Default color of dead cells
+pub const DEAD: Color = (0, 0, 0);
+
 /// A game of life (or other life-like) has its own rules in addition to
 /// other information
 pub struct LifeLike {
     rules: Rules,
-    field: Canvas<Cell>,
+    neigh: Neighborhood,
+    /// Neighborhood in which survival is counted, `neigh` if not set
+    survival_neigh: Option<Neighborhood>,
+    /// What lies beyond the top and bottom edges
+    vertical: Boundary,
+    /// What lies beyond the left and right edges
+    horizontal: Boundary,
+    /// Current state
+    curr: Bits,
+    /// Buffer in which the next state is computed, then swapped with `curr`
+    succ: Bits,
+    /// Cells that keep their state whatever their neighbors,
+    /// only allocated once `set_frozen` is called
+    frozen: Option<Bits>,
+    /// Number of generations each cell has been alive for,
+    /// only tracked once age colors are set
+    ages: Option<Vec<usize>>,
+    /// Number of births and deaths of each cell,
+    /// only tracked once `track_activity` is called
+    activity: Option<Vec<usize>>,
+    /// Changes made to the current state, applied by `update`
+    edits: Vec<([usize; 2], bool)>,
+    /// Colors of live and dead cells
+    colors: (Color, Color),
+    /// Gradient for live cells according to their age:
+    /// from a color to another over a number of generations
+    age_colors: Option<(Color, Color, usize)>,
     hgt: usize,
     wth: usize,
+    /// Number of calls to `next`
+    gen: usize,
     cnt: usize,
     born: usize,
     dead: usize,
+    logger: Option<StatsLogger>,
+    /// Report progress when rendering, see `Config::set_progress`
+    verbose: bool,
+    /// What to do with the rules declared by RLE patterns
+    on_mismatch: RuleMismatch,
+    /// What to do with patterns that overrun the canvas
+    placement: Placement,
+    /// Stop `advance` and `run` when all cells are dead
+    halt_on_extinction: bool,
+    /// Count Moore neighbors from sums over columns, see `use_row_sums`
+    row_sums: bool,
+    /// Probability for a cell that should be alive to actually be,
+    /// and the generator that decides it, see `set_noise`
+    noise: Option<(f64, StdRng)>,
+    /// Hash and packed contents of the most recent states, oldest first
+    history: VecDeque<(u64, Bits)>,
+    /// States before the most recent calls to `next`, oldest first
+    undo: VecDeque<Bits>,
+    /// Maximum length of `undo`, 0 to disable `step_back`
+    undo_depth: usize,
 }
 
+/// Number of past generations remembered for cycle detection
+pub const HISTORY: usize = 64;
+
 impl LifeLike {
-    /// Rules are given at the initialization, and cannot be modified
-    pub fn new(hgt: usize, wth: usize, rules: &str) -> Self {
+    /// Rules are given at the initialization.
+    /// Panics if the rules are malformed or mention more neighbors
+    /// than `neigh` contains, or if the board has no cells.
+    pub fn new(
+        hgt: usize,
+        wth: usize,
+        rules: &str,
+        neigh: Neighborhood,
+        boundary: Boundary,
+    ) -> Self {
+        Self::try_new(hgt, wth, rules, neigh, boundary).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Start building an automaton with more settings than `new` takes
+    pub fn builder() -> LifeLikeBuilder {
+        LifeLikeBuilder::new()
+    }
+
+    /// Same as `new`, but reports invalid rules and dimensions
+    /// instead of panicking
+    pub fn try_new(
+        hgt: usize,
+        wth: usize,
+        rules: &str,
+        neigh: Neighborhood,
+        boundary: Boundary,
+    ) -> Result<Self, RuleParseError> {
+        if hgt == 0 || wth == 0 {
+            return Err(RuleParseError::EmptyBoard(hgt, wth));
+        }
+        if let (_, Some(expected)) = Neighborhood::split_suffix(rules) {
+            if expected != neigh {
+                return Err(RuleParseError::WrongNeighborhood(expected));
+            }
+        }
+        let rules = Rules::try_new(rules)?;
+        rules.validate(neigh)?;
+        Ok(Self::with_rules(hgt, wth, rules, neigh, boundary))
+    }
+
+    /// Rules given by an arbitrary function of the current state of a cell
+    /// and of its number of live neighbors, that tells whether it is alive
+    /// at the next generation.
+    /// The function is tabulated once, so the simulation is as fast as
+    /// with B/S rules.
+    /// Panics if the board has no cells.
+    pub fn with_transition<F: Fn(bool, usize) -> bool>(
+        hgt: usize,
+        wth: usize,
+        f: F,
+        neigh: Neighborhood,
+        boundary: Boundary,
+    ) -> Self {
+        let rules = Rules::from_fn(|alive, n| n <= neigh.size() && f(alive, n));
+        Self::with_rules(hgt, wth, rules, neigh, boundary)
+    }
+
+    /// Empty board with already parsed rules
+    fn with_rules(
+        hgt: usize,
+        wth: usize,
+        rules: Rules,
+        neigh: Neighborhood,
+        boundary: Boundary,
+    ) -> Self {
+        assert!(
+            hgt > 0 && wth > 0,
+            "{}",
+            RuleParseError::EmptyBoard(hgt, wth)
+        );
         Self {
-            rules: Rules::new(rules),
-            field: Canvas::new(hgt, wth, Cell::new()),
+            rules,
+            neigh,
+            vertical: boundary,
+            horizontal: boundary,
+            curr: Bits::new(hgt, wth),
+            succ: Bits::new(hgt, wth),
+            frozen: None,
+            ages: None,
+            activity: None,
+            survival_neigh: None,
+            edits: Vec::new(),
+            colors: (ALIVE, DEAD),
+            age_colors: None,
             hgt,
             wth,
+            gen: 0,
             cnt: 0,
             born: 0,
             dead: 0,
+            logger: None,
+            verbose: true,
+            on_mismatch: RuleMismatch::Warn,
+            placement: Placement::Lenient,
+            halt_on_extinction: false,
+            row_sums: false,
+            noise: None,
+            history: VecDeque::with_capacity(HISTORY + 1),
+            undo: VecDeque::new(),
+            undo_depth: 0,
+        }
+    }
+
+    /// Kill all cells and reset the statistics, but keep the rules,
+    /// dimensions and all settings, including which cells are frozen.
+    /// Pending changes, the history of recent states and the states kept
+    /// for `step_back` are dropped as well.
+    pub fn clear(&mut self) {
+        self.curr.clear();
+        self.succ.clear();
+        self.edits.clear();
+        if let Some(ages) = &mut self.ages {
+            ages.iter_mut().for_each(|a| *a = 0);
+        }
+        if let Some(activity) = &mut self.activity {
+            activity.iter_mut().for_each(|a| *a = 0);
+        }
+        self.cnt = 0;
+        self.born = 0;
+        self.dead = 0;
+        self.history.clear();
+        self.undo.clear();
+    }
+
+    /// Roll the whole board by `di` lines down and `dj` columns right,
+    /// cells pushed over an edge reappearing on the opposite one
+    /// whatever the boundary conditions, e.g. to keep a drifting spaceship
+    /// in view. Ages, activity, frozen cells and pending changes move
+    /// with their cells,
+    /// the history of recent states and the states kept for `step_back`
+    /// are dropped.
+    pub fn shift(&mut self, di: isize, dj: isize) {
+        let (hgt, wth) = (self.hgt, self.wth);
+        let di = di.rem_euclid(hgt as isize) as usize;
+        let dj = dj.rem_euclid(wth as isize) as usize;
+        let moved = |[i, j]: [usize; 2]| [(i + di) % hgt, (j + dj) % wth];
+        self.succ.clear();
+        for idx in self.curr.ones() {
+            self.succ.set(moved(idx), true);
+        }
+        std::mem::swap(&mut self.curr, &mut self.succ);
+        if let Some(frozen) = &mut self.frozen {
+            self.succ.clear();
+            for idx in frozen.ones() {
+                self.succ.set(moved(idx), true);
+            }
+            std::mem::swap(frozen, &mut self.succ);
+        }
+        self.succ.clear();
+        for (idx, _) in &mut self.edits {
+            *idx = moved(*idx);
         }
+        // Whole lines are rotated first, then the cells within each line
+        for tab in self.ages.iter_mut().chain(self.activity.iter_mut()) {
+            tab.rotate_right(di * wth);
+            tab.chunks_mut(wth).for_each(|line| line.rotate_right(dj));
+        }
+        self.history.clear();
+        self.undo.clear();
+    }
+
+    /// Clear the board then birth cells at random as with `init_rand`
+    pub fn fill(&mut self, p: f64) {
+        self.clear();
+        self.init_rand(p);
     }
 
     /// Birth cells at random all over the canvas.
     /// p is the probability for any cell of being born.
     pub fn init_rand(&mut self, p: f64) {
-        let mut rng = rand::thread_rng();
-        for i in 0..self.hgt {
-            for j in 0..self.wth {
+        self.fill_rand(0..self.hgt, 0..self.wth, p, &mut rand::thread_rng());
+    }
+
+    /// Same as `init_rand`, but reproducible: the same seed, dimensions
+    /// and probability always yield the same board.
+    pub fn init_rand_seeded(&mut self, p: f64, seed: u64) {
+        self.fill_rand(
+            0..self.hgt,
+            0..self.wth,
+            p,
+            &mut StdRng::seed_from_u64(seed),
+        );
+    }
+
+    /// Birth cells at random with probability p, but only within an area
+    /// around the center of size given by f
+    /// (proportion of the total dimensions)
+    pub fn init_cluster(&mut self, f: f64, p: f64) {
+        let (lines, cols) = self.cluster(f);
+        self.fill_rand(lines, cols, p, &mut rand::thread_rng());
+    }
+
+    /// Same as `init_cluster`, but reproducible: the same seed, dimensions,
+    /// size and probability always yield the same board.
+    pub fn init_cluster_seeded(&mut self, f: f64, p: f64, seed: u64) {
+        let (lines, cols) = self.cluster(f);
+        self.fill_rand(lines, cols, p, &mut StdRng::seed_from_u64(seed));
+    }
+
+    /// Birth cells at random with probability p within the rectangle
+    /// of `h` lines and `w` columns whose top left corner is `(i0, j0)`.
+    /// Parts of the rectangle outside of the canvas wrap around along axes
+    /// that do so and are dropped otherwise, as when placing a pattern.
+    pub fn init_region(&mut self, i0: isize, j0: isize, h: usize, w: usize, p: f64) {
+        self.fill_region(i0, j0, h, w, p, &mut rand::thread_rng());
+    }
+
+    /// Same as `init_region`, but reproducible: the same seed, region
+    /// and probability always yield the same cells.
+    pub fn init_region_seeded(
+        &mut self,
+        i0: isize,
+        j0: isize,
+        h: usize,
+        w: usize,
+        p: f64,
+        seed: u64,
+    ) {
+        self.fill_region(i0, j0, h, w, p, &mut StdRng::seed_from_u64(seed));
+    }
+
+    fn fill_region<R: Rng>(
+        &mut self,
+        i0: isize,
+        j0: isize,
+        h: usize,
+        w: usize,
+        p: f64,
+        rng: &mut R,
+    ) {
+        for i in i0..i0 + h as isize {
+            for j in j0..j0 + w as isize {
                 if rng.gen::<f64>() < p {
-                    self.field[[i, j]].birth();
+                    self.stamp(i, j, true);
                 }
             }
         }
         self.update();
     }
 
-    /// Birth cells at random with probability p, but only within an area
-    /// around the center of size given by f
-    /// (proportion of the total dimensions)
-    pub fn init_cluster(&mut self, f: f64, p: f64) {
-        let mut rng = rand::thread_rng();
+    /// Lines and columns of the area around the center of size given by f
+    fn cluster(&self, f: f64) -> (Range<usize>, Range<usize>) {
         let lo = |n| (n as f64 * (1. - f) / 2.).floor() as usize;
         let hi = |n| (n as f64 * (1. + f) / 2.).floor() as usize;
-        for i in lo(self.hgt)..hi(self.hgt) {
-            for j in lo(self.wth)..hi(self.wth) {
+        (lo(self.hgt)..hi(self.hgt), lo(self.wth)..hi(self.wth))
+    }
+
+    /// Birth every other cell, starting with the top left corner
+    pub fn init_checkerboard(&mut self) {
+        self.fill_where(|i, j| (i + j) % 2 == 0);
+    }
+
+    /// Birth every cell of one line out of `period`, starting with the first
+    pub fn init_stripes(&mut self, period: usize) {
+        let period = period.max(1);
+        self.fill_where(|i, _| i % period == 0);
+    }
+
+    /// Birth all cells on the edges of the canvas
+    pub fn init_border(&mut self) {
+        let (hgt, wth) = (self.hgt, self.wth);
+        self.fill_where(|i, j| i == 0 || j == 0 || i + 1 == hgt || j + 1 == wth);
+    }
+
+    /// Birth the cells for which `f` is true
+    fn fill_where<F: Fn(usize, usize) -> bool>(&mut self, f: F) {
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                if f(i, j) {
+                    self.edits.push(([i, j], true));
+                }
+            }
+        }
+        self.update();
+    }
+
+    /// Birth cells of an area at random with probability p
+    fn fill_rand<R: Rng>(&mut self, lines: Range<usize>, cols: Range<usize>, p: f64, rng: &mut R) {
+        for i in lines {
+            for j in cols.clone() {
                 if rng.gen::<f64>() < p {
-                    self.field[[i, j]].birth();
+                    self.edits.push(([i, j], true));
                 }
             }
         }
@@ -79,343 +393,2853 @@ impl LifeLike {
 
     /// Add pattern stored in an external file to the canvas.
     /// Auto-detects file extension.
-    /// Works with `*.txt`, `*.lif`, `*.cells`, `*.rle`
-    pub fn add_from_file(&mut self, file: &str, i0: isize, j0: isize, t: Transform) {
-        let data = std::fs::read_to_string(file).unwrap();
-        let mut i = i0;
-        let mut j = j0;
-        match file.split('.').rev().next().unwrap() {
-            // get file extension
-            "txt" => {
-                for c in data.chars() {
-                    match c {
-                        '\n' => {
-                            t.newline(&mut i, &mut j, i0, j0);
-                        }
-                        'x' => {
-                            self.field.mod_idx(i, j).birth();
-                            t.next(&mut i, &mut j);
-                        }
-                        '.' => {
-                            self.field.mod_idx(i, j).kill();
-                            t.next(&mut i, &mut j);
-                        }
-                        ' ' => t.next(&mut i, &mut j),
-                        '\r' => (),
-                        c => panic!("unknown character {}", ascii::escape_default(c as u8)),
-                    }
+    /// Works with `*.txt`, `*.lif`, `*.cells`, `*.rle`,
+    /// Life 1.06 (`*.life`, `*.l`, or `*.lif` starting with `#Life 1.06`)
+    /// and Golly's macrocell format (`*.mc`), as well as their gzip-compressed versions (`*.rle.gz`, ...)
+    /// with the `flate2` feature.
+    pub fn add_from_file(
+        &mut self,
+        file: &str,
+        i0: isize,
+        j0: isize,
+        t: Transform,
+    ) -> Result<(), LoadError> {
+        let (data, ext) = read_pattern(file)?;
+        self.place(&data, ext, file, i0, j0, t)
+    }
+
+    /// Add a pattern given as a string in the format associated with
+    /// the extension `ext` (as in `add_from_file`), without any file access
+    pub fn add_from_str(
+        &mut self,
+        data: &str,
+        ext: &str,
+        i0: isize,
+        j0: isize,
+        t: Transform,
+    ) -> Result<(), LoadError> {
+        self.place(data, ext, &format!("pattern.{}", ext), i0, j0, t)
+    }
+
+    /// Add several patterns stored in external files, each with its own
+    /// origin and transformation, and update the canvas only once.
+    /// An error is wrapped in `LoadError::InItem` to tell which item failed,
+    /// the items before it are still added.
+    pub fn add_patterns(
+        &mut self,
+        items: &[(String, isize, isize, Transform)],
+    ) -> Result<(), LoadError> {
+        let mut res = Ok(());
+        for (k, (file, i0, j0, t)) in items.iter().enumerate() {
+            res = read_pattern(file)
+                .and_then(|(data, ext)| self.stamp_pattern(&data, ext, file, *i0, *j0, *t))
+                .map_err(|e| LoadError::InItem(k, file.clone(), Box::new(e)));
+            if res.is_err() {
+                break;
+            }
+        }
+        self.update();
+        res
+    }
+
+    /// Add a built-in pattern to the canvas
+    pub fn add_pattern(
+        &mut self,
+        p: Pattern,
+        i0: isize,
+        j0: isize,
+        t: Transform,
+    ) -> Result<(), LoadError> {
+        self.place(p.rle(), "rle", &format!("{:?}", p), i0, j0, t)
+    }
+
+    /// Parse a pattern in the format associated with the extension `ext`
+    /// and add it to the canvas.
+    /// `name` identifies the pattern in warnings.
+    fn place(
+        &mut self,
+        data: &str,
+        ext: &str,
+        name: &str,
+        i0: isize,
+        j0: isize,
+        t: Transform,
+    ) -> Result<(), LoadError> {
+        self.stamp_pattern(data, ext, name, i0, j0, t)?;
+        self.update();
+        Ok(())
+    }
+
+    /// Same as `place`, but the changes are only applied by the next `update`
+    fn stamp_pattern(
+        &mut self,
+        data: &str,
+        ext: &str,
+        name: &str,
+        i0: isize,
+        j0: isize,
+        t: Transform,
+    ) -> Result<(), LoadError> {
+        if self.placement == Placement::Strict {
+            self.check_extent(data_extent(data, ext)?, i0, j0, &t)?;
+        }
+        let mut builder = PatternBuilder::new(self, i0, j0, t);
+        match ext {
+            "txt" => parse_txt(data, &mut builder)?,
+            "life" | "l" => parse_life106(data, &mut builder)?,
+            "lif" if data.starts_with(LIFE_106) => parse_life106(data, &mut builder)?,
+            "lif" => parse_plain(data, &mut builder, '#', '*')?,
+            "cells" => parse_plain(data, &mut builder, '!', 'O')?,
+            "rle" => parse_rle(data, &mut builder, name)?,
+            "mc" => parse_macrocell(data, &mut builder, name)?,
+            ext => return Err(LoadError::UnknownExtension(ext.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Add pattern stored in an external file so that it is centered
+    /// on the canvas.
+    /// Fails if the pattern is larger than the canvas.
+    pub fn add_from_file_centered(&mut self, file: &str, t: Transform) -> Result<(), LoadError> {
+        let (data, ext) = read_pattern(file)?;
+        self.place_centered(&data, ext, file, t)
+    }
+
+    /// Same as `add_from_file_centered` for a pattern given as a string
+    /// in the format associated with `ext`, as in `add_from_str`
+    pub fn add_from_str_centered(
+        &mut self,
+        data: &str,
+        ext: &str,
+        t: Transform,
+    ) -> Result<(), LoadError> {
+        self.place_centered(data, ext, &format!("pattern.{}", ext), t)
+    }
+
+    /// Same as `place`, with the origin chosen to center the pattern
+    fn place_centered(
+        &mut self,
+        data: &str,
+        ext: &str,
+        name: &str,
+        t: Transform,
+    ) -> Result<(), LoadError> {
+        let (r0, c0, hgt, wth) = data_extent(data, ext)?;
+        if hgt == 0 || wth == 0 {
+            return Ok(());
+        }
+        let (imin, jmin, imax, jmax) = t.bounds(r0, c0, hgt, wth);
+        let (thgt, twth) = (imax - imin + 1, jmax - jmin + 1);
+        if thgt > self.hgt as isize || twth > self.wth as isize {
+            return Err(LoadError::DoesNotFit(hgt, wth));
+        }
+        let i0 = (self.hgt as isize - thgt) / 2 - imin;
+        let j0 = (self.wth as isize - twth) / 2 - jmin;
+        self.place(data, ext, name, i0, j0, t)
+    }
+
+    /// Compare rules declared by the pattern `name` with those of the
+    /// simulation, and react to a difference as chosen by `set_rule_mismatch`
+    fn check_rule(&mut self, rule: &str, name: &str) -> Result<(), LoadError> {
+        let rules = Rules::try_new(rule).ok().filter(|r| {
+            r.validate_each(self.neigh, self.survival_neighborhood())
+                .is_ok()
+        });
+        if rules == Some(self.rules) {
+            return Ok(());
+        }
+        match (self.on_mismatch, rules) {
+            (RuleMismatch::Ignore, _) => (),
+            (RuleMismatch::Warn, _) => eprintln!("Warning: {} expects rule {}", name, rule),
+            (RuleMismatch::Adopt, Some(rules)) => self.rules = rules,
+            (RuleMismatch::Fail, _) | (RuleMismatch::Adopt, None) => {
+                return Err(LoadError::WrongRule(rule.to_string()))
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that a pattern of the given dimensions placed at `(i0, j0)`
+    /// under `t` lies entirely within the canvas
+    fn check_fits(
+        &self,
+        hgt: usize,
+        wth: usize,
+        i0: isize,
+        j0: isize,
+        t: &Transform,
+    ) -> Result<(), LoadError> {
+        self.check_extent((0, 0, hgt, wth), i0, j0, t)
+    }
+
+    /// Same as `check_fits` for a pattern of extent `(r0, c0, hgt, wth)`
+    /// as given by `pattern_extent`
+    fn check_extent(
+        &self,
+        (r0, c0, hgt, wth): (isize, isize, usize, usize),
+        i0: isize,
+        j0: isize,
+        t: &Transform,
+    ) -> Result<(), LoadError> {
+        if hgt == 0 || wth == 0 {
+            return Ok(());
+        }
+        let (imin, jmin, imax, jmax) = t.bounds(r0, c0, hgt, wth);
+        let inside = |i: isize, n: usize| i >= 0 && i < n as isize;
+        if inside(i0 + imin, self.hgt)
+            && inside(j0 + jmin, self.wth)
+            && inside(i0 + imax, self.hgt)
+            && inside(j0 + jmax, self.wth)
+        {
+            Ok(())
+        } else {
+            Err(LoadError::DoesNotFit(hgt, wth))
+        }
+    }
+
+    /// Average position `(i, j)` of the live cells, `None` if there are none.
+    /// Along an axis that wraps around, the coordinate is the circular mean:
+    /// positions are seen as angles around the axis, so that a cluster
+    /// across an edge is centered near that edge rather than in the middle
+    /// of the board. The result is then in `[0, hgt)` or `[0, wth)`.
+    pub fn center_of_mass(&self) -> Option<(f64, f64)> {
+        if self.cnt == 0 {
+            return None;
+        }
+        let mean = |boundary: Boundary, len: usize, coord: fn((usize, usize)) -> usize| {
+            let len = len as f64;
+            if boundary == Boundary::Toroidal {
+                let tau = 2. * std::f64::consts::PI;
+                let (s, c) = self.live_cells().fold((0., 0.), |(s, c), cell| {
+                    let a = tau * coord(cell) as f64 / len;
+                    (s + a.sin(), c + a.cos())
+                });
+                (f64::atan2(s, c) / tau * len).rem_euclid(len)
+            } else {
+                let s: usize = self.live_cells().map(coord).sum();
+                s as f64 / self.cnt as f64
+            }
+        };
+        Some((
+            mean(self.vertical, self.hgt, |(i, _)| i),
+            mean(self.horizontal, self.wth, |(_, j)| j),
+        ))
+    }
+
+    /// Smallest rectangle `(min_i, min_j, max_i, max_j)` that contains all
+    /// live cells (bounds included), `None` if there are none
+    pub fn live_bounds(&self) -> Option<(usize, usize, usize, usize)> {
+        if self.cnt == 0 {
+            return None;
+        }
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for (i, j) in self.live_cells() {
+            bounds = Some(match bounds {
+                None => (i, j, i, j),
+                Some((imin, jmin, imax, jmax)) => {
+                    (imin.min(i), jmin.min(j), imax.max(i), jmax.max(j))
                 }
+            });
+        }
+        bounds
+    }
+
+    /// Positions `(i, j)` of all live cells, line by line
+    pub fn live_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.curr.ones().map(|[i, j]| (i, j))
+    }
+
+    /// Groups of live cells connected through the Moore neighborhood,
+    /// e.g. to census the objects left by a soup once it has stabilized.
+    /// Cells are adjacent across the edges along axes that wrap around.
+    /// Each group is sorted line by line, and groups are ordered
+    /// by their first cell. Only live cells are visited, so sparse boards
+    /// are handled quickly.
+    pub fn connected_components(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut seen = Bits::new(self.hgt, self.wth);
+        let mut res = Vec::new();
+        let mut stack = Vec::new();
+        for start in self.curr.ones() {
+            if seen.get(start) {
+                continue;
             }
-            "lif" => {
-                let mut it = data.chars();
-                loop {
-                    match it.next() {
-                        None => break,
-                        Some('#') => loop {
-                            match it.next() {
-                                None => break,
-                                Some('\n') => break,
-                                Some(_) => (),
-                            }
-                        },
-                        Some('\n') => {
-                            t.newline(&mut i, &mut j, i0, j0);
-                        }
-                        Some('*') => {
-                            self.field.mod_idx(i, j).birth();
-                            t.next(&mut i, &mut j);
+            seen.set(start, true);
+            stack.push(start);
+            let mut component = Vec::new();
+            while let Some([i, j]) = stack.pop() {
+                component.push((i, j));
+                for &(mvi, mvj) in Neighborhood::Moore.offsets(i) {
+                    if let Some(idx) = self.index_move(i, j, mvi, mvj) {
+                        if self.curr.get(idx) && !seen.get(idx) {
+                            seen.set(idx, true);
+                            stack.push(idx);
                         }
-                        Some('.') => {
-                            self.field.mod_idx(i, j).kill();
-                            t.next(&mut i, &mut j);
-                        }
-                        Some('\r') => (),
-                        Some(c) => panic!("unknown character {}", ascii::escape_default(c as u8)),
                     }
                 }
             }
-            "cells" => {
-                let mut it = data.chars();
-                loop {
-                    match it.next() {
-                        None => break,
-                        Some('!') => loop {
-                            match it.next() {
-                                None => break,
-                                Some('\n') => break,
-                                Some(_) => (),
-                            }
-                        },
-                        Some('\n') => {
-                            t.newline(&mut i, &mut j, i0, j0);
-                        }
-                        Some('O') => {
-                            self.field.mod_idx(i, j).birth();
-                            t.next(&mut i, &mut j);
-                        }
-                        Some('.') => {
-                            self.field.mod_idx(i, j).kill();
-                            t.next(&mut i, &mut j);
-                        }
-                        Some('\r') => (),
-                        Some(c) => panic!(
-                            "unknown character `{}` ({})",
-                            ascii::escape_default(c as u8),
-                            c as u32
-                        ),
-                    }
+            component.sort_unstable();
+            res.push(component);
+        }
+        res
+    }
+
+    /// Apgcode of the object formed by all live cells, as used by Catagolue
+    /// to identify objects: `xs<population>_` for still lifes,
+    /// `xp<period>_` for oscillators and `xq<period>_` for spaceships,
+    /// followed by the phase and orientation that give the shortest
+    /// code in the extended Wechsler format (e.g. `xs4_33` for a block,
+    /// `xq4_153` for a glider).
+    /// See `apgcode_of` for when there is no apgcode.
+    pub fn apgcode(&self) -> Option<String> {
+        let cells: Vec<_> = self.live_cells().collect();
+        self.apgcode_of(&cells)
+    }
+
+    /// Apgcode of a single object, e.g. one of `connected_components`,
+    /// as if it were alone on an unbounded plane.
+    /// `None` if the object does not come back to its initial state
+    /// within `MAX_APG_PERIOD` generations, or if the rules cannot be
+    /// followed on an unbounded plane (B0, or different neighborhoods
+    /// for birth and survival, or `Neighborhood::Hex` whose symmetries
+    /// differ from those of the square grid).
+    pub fn apgcode_of(&self, cells: &[(usize, usize)]) -> Option<String> {
+        if self.neigh == Neighborhood::Hex
+            || self.survival_neighborhood() != self.neigh
+            || self.rules.b[0]
+        {
+            return None;
+        }
+        if cells.is_empty() {
+            return Some(String::from("xs0_0"));
+        }
+        // Objects may straddle the edges along axes that wrap around
+        let is = unwrap_axis(cells.iter().map(|c| c.0), self.hgt, self.vertical);
+        let js = unwrap_axis(cells.iter().map(|c| c.1), self.wth, self.horizontal);
+        let mut plane = SparseLife::new(1, 1, &self.rules.notation(), self.neigh);
+        for (&i, &j) in is.iter().zip(&js) {
+            plane.set(i, j, true);
+        }
+        let normalized = |plane: &SparseLife| {
+            let mut cells: Vec<_> = plane.live_cells().collect();
+            let imin = cells.iter().map(|c| c.0).min().unwrap_or(0);
+            let jmin = cells.iter().map(|c| c.1).min().unwrap_or(0);
+            cells.iter_mut().for_each(|c| *c = (c.0 - imin, c.1 - jmin));
+            cells.sort_unstable();
+            (cells, (imin, jmin))
+        };
+        let (start, corner) = normalized(&plane);
+        let mut phases = vec![start.clone()];
+        let mut moved = None;
+        for _ in 0..MAX_APG_PERIOD {
+            plane.next();
+            let (phase, c) = normalized(&plane);
+            if phase == start {
+                moved = Some(c != corner);
+                break;
+            }
+            phases.push(phase);
+        }
+        let prefix = match moved? {
+            true => format!("xq{}", phases.len()),
+            false if phases.len() == 1 => format!("xs{}", start.len()),
+            false => format!("xp{}", phases.len()),
+        };
+        let code = phases
+            .iter()
+            .flat_map(|phase| {
+                (0..8).map(move |sym| {
+                    let mut cells: Vec<_> = phase
+                        .iter()
+                        .map(|&(i, j)| {
+                            let (i, j) = if sym & 4 != 0 { (j, i) } else { (i, j) };
+                            let i = if sym & 1 != 0 { -i } else { i };
+                            let j = if sym & 2 != 0 { -j } else { j };
+                            (i, j)
+                        })
+                        .collect();
+                    wechsler(&mut cells)
+                })
+            })
+            .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+            .unwrap();
+        Some(format!("{}_{}", prefix, code))
+    }
+
+    /// Save the live cells to a file in the RLE format,
+    /// cropped to the smallest rectangle that contains them all
+    pub fn save_rle(&self, path: &str, rule: &Rules) -> io::Result<()> {
+        let mut f = BufWriter::new(File::create(path)?);
+        let (imin, jmin, imax, jmax) = match self.live_bounds() {
+            None => {
+                writeln!(f, "x = 0, y = 0, rule = {}", rule)?;
+                writeln!(f, "!")?;
+                return f.flush();
+            }
+            Some(bounds) => bounds,
+        };
+        writeln!(
+            f,
+            "x = {}, y = {}, rule = {}",
+            jmax - jmin + 1,
+            imax - imin + 1,
+            rule
+        )?;
+        // runs of identical tags, trailing dead cells of each line are omitted
+        let mut runs: Vec<(usize, char)> = Vec::new();
+        let push = |runs: &mut Vec<(usize, char)>, tag: char| match runs.last_mut() {
+            Some((n, t)) if *t == tag => *n += 1,
+            _ => runs.push((1, tag)),
+        };
+        for i in imin..=imax {
+            if i > imin {
+                if let Some((_, 'b')) = runs.last() {
+                    runs.pop();
                 }
+                push(&mut runs, '$');
             }
-            "rle" => {
-                let mut it = data.chars();
-                let mut cnt = 0;
-                loop {
-                    match it.next() {
-                        None => break,
-                        Some('#') | Some('x') => loop {
-                            // '#' is a comment,
-                            // 'x' marks the start of an 'x = {}, y = {}' that this implementation chooses to ignore
-                            match it.next() {
-                                None => break,
-                                Some('\n') => break,
-                                Some(_) => (),
-                            }
-                        },
-                        Some('$') => {
-                            if cnt == 0 {
-                                cnt = 1;
-                            }
-                            for _ in 0..cnt {
-                                t.newline(&mut i, &mut j, i0, j0);
-                            }
-                            cnt = 0;
-                        }
-                        Some('o') => {
-                            if cnt == 0 {
-                                cnt = 1;
-                            }
-                            for _ in 0..cnt {
-                                self.field.mod_idx(i, j).birth();
-                                t.next(&mut i, &mut j);
-                            }
-                            cnt = 0;
-                        }
-                        Some('b') => {
-                            if cnt == 0 {
-                                cnt = 1;
-                            }
-                            for _ in 0..cnt {
-                                self.field.mod_idx(i, j).kill();
-                                t.next(&mut i, &mut j);
-                            }
-                            cnt = 0;
-                        }
-                        Some(d @ '0'..='9') => {
-                            cnt = cnt * 10 + d.to_digit(10).unwrap();
-                        }
-                        Some('!') => break,
-                        Some('\r') => (),
-                        Some('\n') => (),
-                        Some(c) => panic!(
-                            "unknown character `{}` ({})",
-                            ascii::escape_default(c as u8),
-                            c as u32
-                        ),
-                    }
+            for j in jmin..=jmax {
+                let tag = if self.curr.get([i, j]) { 'o' } else { 'b' };
+                push(&mut runs, tag);
+            }
+        }
+        if let Some((_, 'b')) = runs.last() {
+            runs.pop();
+        }
+        // lines of RLE files should not exceed 70 characters
+        let mut len = 0;
+        for (n, tag) in runs {
+            let token = if n == 1 {
+                tag.to_string()
+            } else {
+                format!("{}{}", n, tag)
+            };
+            if len + token.len() > 70 {
+                writeln!(f)?;
+                len = 0;
+            }
+            len += token.len();
+            write!(f, "{}", token)?;
+        }
+        writeln!(f, "!")?;
+        f.flush()
+    }
+
+    /// Save the live cells to a file in the plaintext (`.cells`) format,
+    /// cropped to the smallest rectangle that contains them all.
+    /// `name` is written in a `!Name:` comment if given.
+    pub fn save_cells(&self, path: &str, name: Option<&str>) -> io::Result<()> {
+        let mut f = BufWriter::new(File::create(path)?);
+        match name {
+            Some(name) => writeln!(f, "!Name: {}", name)?,
+            None => writeln!(f, "!")?,
+        }
+        if let Some((imin, jmin, imax, jmax)) = self.live_bounds() {
+            for i in imin..=imax {
+                let line: String = (jmin..=jmax)
+                    .map(|j| if self.curr.get([i, j]) { 'O' } else { '.' })
+                    .collect();
+                writeln!(f, "{}", line.trim_end_matches('.'))?;
+            }
+        }
+        f.flush()
+    }
+
+    /// Save the full state of the simulation to a JSON file,
+    /// from which it can be resumed with `load_state`
+    #[cfg(feature = "serde")]
+    pub fn save_state(&self, path: &str) -> Result<(), LoadError> {
+        let cells = (0..self.hgt)
+            .map(|i| {
+                (0..self.wth)
+                    .map(|j| if self.curr.get([i, j]) { 'O' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        let snapshot = Snapshot {
+            rules: self.rules,
+            neigh: self.neigh,
+            survival_neigh: self.survival_neigh,
+            boundary: self.vertical,
+            horizontal: Some(self.horizontal),
+            hgt: self.hgt,
+            wth: self.wth,
+            cells,
+            frozen: self
+                .frozen
+                .iter()
+                .flat_map(Bits::ones)
+                .map(|[i, j]| (i, j))
+                .collect(),
+            gen: self.gen,
+            noise: self.noise.as_ref().map(|&(q, _)| q),
+            cnt: self.cnt,
+            born: self.born,
+            dead: self.dead,
+        };
+        let f = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(f, &snapshot).map_err(|e| LoadError::InvalidState(e.to_string()))
+    }
+
+    /// Resume a simulation saved by `save_state`.
+    /// Colors are reset to their defaults.
+    /// The probability of noise is restored but not the state of its
+    /// generator, which is seeded anew as by `set_noise`: with noise,
+    /// the next generations differ from those of the saved simulation.
+    #[cfg(feature = "serde")]
+    pub fn load_state(path: &str) -> Result<Self, LoadError> {
+        let f = io::BufReader::new(File::open(path)?);
+        let snapshot: Snapshot =
+            serde_json::from_reader(f).map_err(|e| LoadError::InvalidState(e.to_string()))?;
+        if snapshot.hgt == 0 || snapshot.wth == 0 {
+            return Err(LoadError::InvalidState(
+                RuleParseError::EmptyBoard(snapshot.hgt, snapshot.wth).to_string(),
+            ));
+        }
+        let mut game = Self::with_rules(
+            snapshot.hgt,
+            snapshot.wth,
+            snapshot.rules,
+            snapshot.neigh,
+            snapshot.boundary,
+        );
+        game.survival_neigh = snapshot.survival_neigh;
+        game.horizontal = snapshot.horizontal.unwrap_or(snapshot.boundary);
+        if snapshot.cells.len() != game.hgt {
+            return Err(LoadError::InvalidState(String::from(
+                "wrong number of lines",
+            )));
+        }
+        for (i, line) in snapshot.cells.iter().enumerate() {
+            if line.chars().count() != game.wth {
+                return Err(LoadError::InvalidState(format!(
+                    "wrong length of line {}",
+                    i
+                )));
+            }
+            for (j, c) in line.chars().enumerate() {
+                match c {
+                    'O' => game.edits.push(([i, j], true)),
+                    '.' => (),
+                    c => return Err(LoadError::UnexpectedChar(c, j)),
                 }
             }
-            ext => panic!("{} is not recognized as a valid extension", ext),
         }
-        self.update();
+        for &(i, j) in &snapshot.frozen {
+            if i >= game.hgt || j >= game.wth {
+                return Err(LoadError::InvalidState(format!(
+                    "frozen cell ({}, {}) outside of the board",
+                    i, j
+                )));
+            }
+            game.set_frozen(i, j, true);
+        }
+        game.update();
+        if let Some(q) = snapshot.noise {
+            game.set_noise(q);
+        }
+        game.gen = snapshot.gen;
+        game.cnt = snapshot.cnt;
+        game.born = snapshot.born;
+        game.dead = snapshot.dead;
+        Ok(game)
     }
 
-    /// Set each cell to its next state and count number of cells of each type
+    /// Apply the pending changes made by the initializers
+    /// and count number of cells of each type.
+    /// If any cell changed, the history of recent states is dropped,
+    /// since the board did not evolve into its new state.
     pub fn update(&mut self) {
         self.born = 0;
         self.dead = 0;
-        for i in 0..self.hgt {
-            for j in 0..self.wth {
-                self.field[[i, j]].update(&mut self.born, &mut self.dead);
+        for (idx, alive) in std::mem::take(&mut self.edits) {
+            if self.curr.get(idx) != alive {
+                if alive {
+                    self.born += 1;
+                } else {
+                    self.dead += 1;
+                }
+                self.curr.set(idx, alive);
+                if let Some(ages) = &mut self.ages {
+                    ages[idx[0] * self.wth + idx[1]] = 0;
+                }
+                if let Some(activity) = &mut self.activity {
+                    activity[idx[0] * self.wth + idx[1]] += 1;
+                }
             }
         }
+        self.finish();
+        if self.born + self.dead > 0 {
+            self.history.clear();
+        }
+    }
+
+    /// Adjust the population once `born` and `dead` are known
+    /// and record statistics
+    fn finish(&mut self) {
         self.cnt += self.born;
         self.cnt -= self.dead;
+        if let Some(logger) = &mut self.logger {
+            logger.record(self.gen, self.cnt, self.born, self.dead);
+        }
+    }
+
+    /// Push the current state to the history of recent states
+    fn remember(&mut self) {
+        let mut hasher = DefaultHasher::new();
+        self.curr.words.hash(&mut hasher);
+        if self.history.len() > HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back((hasher.finish(), self.curr.clone()));
+    }
+
+    /// Period of the cycle the board is in (1 for a still life),
+    /// if the current state already occurred within the last `HISTORY` generations
+    pub fn detect_period(&self) -> Option<usize> {
+        let (hash, grid) = self.history.back()?;
+        self.history
+            .iter()
+            .rev()
+            .skip(1)
+            .position(|(h, g)| h == hash && g == grid)
+            .map(|k| k + 1)
+    }
+
+    /// Smallest number of generations, at most `max`, after which the board
+    /// is exactly in its current state again, without any translation
+    /// (1 for a still life, 2 for a blinker).
+    /// The generations are computed without noise, statistics or history,
+    /// and the board is restored to its current state afterwards.
+    pub fn oscillator_period(&mut self, max: usize) -> Option<usize> {
+        let start = self.curr.clone();
+        let saved = (self.gen, self.cnt, self.born, self.dead);
+        let logger = self.logger.take();
+        let noise = self.noise.take();
+        let ages = self.ages.take();
+        let activity = self.activity.take();
+        let history = std::mem::take(&mut self.history);
+        let undo_depth = std::mem::replace(&mut self.undo_depth, 0);
+        let period = (1..=max).find(|_| {
+            self.step(None);
+            self.curr == start
+        });
+        self.curr = start;
+        (self.gen, self.cnt, self.born, self.dead) = saved;
+        self.logger = logger;
+        self.noise = noise;
+        self.ages = ages;
+        self.activity = activity;
+        self.history = history;
+        self.undo_depth = undo_depth;
+        period
     }
 
-    /// 2D Array access with looping around the edges.
-    /// Only works with direct neighbors.
-    fn index_move(&self, i: usize, j: usize, mvi: isize, mvj: isize) -> [usize; 2] {
-        let (mut i, mut j) = (i, j);
-        match mvi {
-            -1 => {
-                if i == 0 {
-                    i = self.hgt - 1;
+    /// Period and displacement `(period, di, dj)` of a spaceship: the current
+    /// state is the state of `period` generations ago moved by `di` lines
+    /// and `dj` columns, within the last `HISTORY` generations.
+    /// Along an axis that wraps around, the displacement may cross the edges,
+    /// and is given as the shortest one (e.g. `-1` rather than `wth - 1`).
+    /// Returns `None` if no such earlier state exists, which includes
+    /// still lifes and oscillators that do not move.
+    pub fn detect_spaceship(&self) -> Option<(usize, isize, isize)> {
+        let (hgt, wth) = (self.hgt as isize, self.wth as isize);
+        let wraps = (
+            self.vertical == Boundary::Toroidal,
+            self.horizontal == Boundary::Toroidal,
+        );
+        let torus = wraps.0 || wraps.1;
+        let live: Vec<_> = self.curr.ones().collect();
+        if live.is_empty() {
+            return None;
+        }
+        let shifted = |[i, j]: [usize; 2], (di, dj): (isize, isize)| {
+            let axis = |i: isize, n: isize, wrap: bool| {
+                if wrap {
+                    Some(i.rem_euclid(n) as usize)
                 } else {
-                    i -= 1;
+                    Some(i as usize).filter(|_| 0 <= i && i < n)
                 }
+            };
+            Some([
+                axis(i as isize + di, hgt, wraps.0)?,
+                axis(j as isize + dj, wth, wraps.1)?,
+            ])
+        };
+        let shortest = |d: isize, n: isize, wrap: bool| {
+            let d = if wrap { d.rem_euclid(n) } else { d };
+            if wrap && 2 * d > n {
+                d - n
+            } else {
+                d
             }
-            1 => {
-                if i == self.hgt - 1 {
-                    i = 0
-                } else {
-                    i += 1;
+        };
+        for (k, (_, past)) in self.history.iter().rev().skip(1).enumerate() {
+            if past.count() != live.len() {
+                continue;
+            }
+            let anchor = past.ones().next()?;
+            // Without wrapping, the first cells in reading order match.
+            // With wrapping, the first cell of the past state
+            // may have moved to any live cell.
+            let targets = if torus { &live[..] } else { &live[..1] };
+            for &target in targets {
+                let d = (
+                    target[0] as isize - anchor[0] as isize,
+                    target[1] as isize - anchor[1] as isize,
+                );
+                let d = (shortest(d.0, hgt, wraps.0), shortest(d.1, wth, wraps.1));
+                if d != (0, 0)
+                    && past
+                        .ones()
+                        .all(|c| shifted(c, d).is_some_and(|c| self.curr.get(c)))
+                {
+                    return Some((k + 1, d.0, d.1));
+                }
+            }
+        }
+        None
+    }
+
+    /// Calculate `n` generations without rendering them
+    /// (fewer if the board dies out and `set_halt_on_extinction` was called).
+    /// Returns the final population and the total numbers of births and deaths.
+    pub fn advance(&mut self, n: usize) -> (usize, usize, usize) {
+        let (mut born, mut dead) = (0, 0);
+        for _ in 0..n {
+            if self.halt_on_extinction && self.is_extinct() {
+                break;
+            }
+            self.next();
+            born += self.born;
+            dead += self.dead;
+        }
+        (self.cnt, born, dead)
+    }
+
+    /// Calculate up to `max_gen` generations, calling `f` with the updated state
+    /// and its generation number after each of them.
+    /// Stops early as soon as `f` returns `false`,
+    /// or when the board dies out if `set_halt_on_extinction` was called.
+    pub fn run<F: FnMut(&LifeLike, usize) -> bool>(&mut self, max_gen: usize, mut f: F) {
+        for _ in 0..max_gen {
+            if self.halt_on_extinction && self.is_extinct() {
+                break;
+            }
+            self.next();
+            if !f(self, self.gen) {
+                break;
+            }
+        }
+    }
+
+    /// Calculate `generations` generations, rendering the current state
+    /// before the first one and then once every `every` generations.
+    /// Stops early when the board dies out if `set_halt_on_extinction`
+    /// was called. Returns the number of frames written,
+    /// or stops at the first frame that could not be written.
+    pub fn run_render(
+        &mut self,
+        cfg: &mut crate::Config,
+        generations: usize,
+        every: usize,
+    ) -> io::Result<usize> {
+        let every = every.max(1);
+        let mut frames = 0;
+        for k in 0..generations {
+            if k % every == 0 {
+                self.render(cfg)?;
+                frames += 1;
+            }
+            if self.halt_on_extinction && self.is_extinct() {
+                break;
+            }
+            self.next();
+        }
+        Ok(frames)
+    }
+
+    /// Make `advance`, `run` and `run_render` stop as soon as no cell is alive
+    /// (they don't by default)
+    pub fn set_halt_on_extinction(&mut self, halt: bool) {
+        self.halt_on_extinction = halt;
+    }
+
+    /// Whether all cells are dead
+    pub fn is_extinct(&self) -> bool {
+        self.cnt == 0
+    }
+
+    /// Whether all cells are alive
+    pub fn is_full(&self) -> bool {
+        self.cnt == self.hgt * self.wth
+    }
+
+    /// Step until the board becomes periodic or `max_gen` generations have passed.
+    /// Returns the period found, if any.
+    pub fn run_until_stable(&mut self, max_gen: usize) -> Option<usize> {
+        for _ in 0..max_gen {
+            self.next();
+            if let Some(period) = self.detect_period() {
+                return Some(period);
+            }
+        }
+        None
+    }
+
+    /// Step until the fraction of cells that change state (see `activity`)
+    /// has stayed below `epsilon` for `k` consecutive generations,
+    /// or `max` generations have passed, e.g. for noisy boards that never
+    /// become exactly periodic. Returns the number of generations calculated.
+    pub fn run_until_quiet(&mut self, epsilon: f64, k: usize, max: usize) -> usize {
+        let mut quiet = 0;
+        for gen in 0..max {
+            if quiet >= k {
+                return gen;
+            }
+            self.next();
+            if self.activity() < epsilon {
+                quiet += 1;
+            } else {
+                quiet = 0;
+            }
+        }
+        max
+    }
+
+    /// Record statistics of every subsequent update,
+    /// or stop recording with `None`.
+    /// Errors while writing are only reported by `flush_logger`.
+    pub fn set_logger(&mut self, logger: Option<StatsLogger>) {
+        self.logger = logger;
+    }
+
+    /// Write the statistics recorded so far, see `StatsLogger::flush`
+    pub fn flush_logger(&mut self) -> io::Result<()> {
+        match &mut self.logger {
+            Some(logger) => logger.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Choose whether `render` reports progress (it does by default),
+    /// to stderr unless another callback is given to `Config::set_progress`
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    /// Choose what happens when an RLE pattern declares different rules
+    /// than those of the simulation (a warning by default)
+    pub fn set_rule_mismatch(&mut self, on_mismatch: RuleMismatch) {
+        self.on_mismatch = on_mismatch;
+    }
+
+    /// Choose what happens when a pattern does not fit on the canvas
+    /// (lenient by default)
+    pub fn set_placement(&mut self, placement: Placement) {
+        self.placement = placement;
+    }
+
+    /// Current rules of the simulation
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Count neighbors in `birth` for dead cells and in `survival`
+    /// for live cells, e.g. to be born on the Moore neighborhood but survive
+    /// on the von Neumann one. Fails if a count of the rules cannot be
+    /// reached in its neighborhood.
+    /// Since the rules are checked against the neighborhood given to `new`,
+    /// construct the board with the Moore neighborhood to use larger counts.
+    pub fn set_neighborhoods(
+        &mut self,
+        birth: Neighborhood,
+        survival: Neighborhood,
+    ) -> Result<(), RuleParseError> {
+        self.rules.validate_each(birth, survival)?;
+        self.neigh = birth;
+        self.survival_neigh = if survival == birth {
+            None
+        } else {
+            Some(survival)
+        };
+        Ok(())
+    }
+
+    /// Neighborhood in which survival is counted
+    pub fn survival_neighborhood(&self) -> Neighborhood {
+        self.survival_neigh.unwrap_or(self.neigh)
+    }
+
+    /// Count neighbors with a sliding window instead of reading
+    /// the 8 neighbors of each cell: for every line, the live cells of each
+    /// column are summed over the line and the two next to it, and the count
+    /// of a cell is the sum of three consecutive columns minus the cell itself.
+    /// Results are the same, but dense boards are computed faster.
+    /// Only applies while both neighborhoods are Moore.
+    pub fn use_row_sums(&mut self, on: bool) {
+        self.row_sums = on;
+    }
+
+    /// Make the automaton stochastic, as if subject to thermal noise:
+    /// after the rules decide that a cell should be born or survive,
+    /// it only does so with probability `q`, and is dead otherwise.
+    /// Deaths are unaffected, and `q >= 1` removes the noise.
+    /// Generations are not reproducible, see `set_noise_seeded` for that.
+    pub fn set_noise(&mut self, q: f64) {
+        self.set_noise_with(q, StdRng::from_rng(rand::thread_rng()).unwrap());
+    }
+
+    /// Same as `set_noise`, but reproducible: the same seed and board
+    /// always yield the same generations.
+    pub fn set_noise_seeded(&mut self, q: f64, seed: u64) {
+        self.set_noise_with(q, StdRng::seed_from_u64(seed));
+    }
+
+    fn set_noise_with(&mut self, q: f64, rng: StdRng) {
+        self.noise = if q < 1. { Some((q, rng)) } else { None };
+    }
+
+    /// Change what lies beyond the edges of the canvas,
+    /// effective from the next generation on
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.set_boundaries(boundary, boundary);
+    }
+
+    /// Same as `set_boundary`, with `vertical` beyond the top and bottom
+    /// edges and `horizontal` beyond the left and right ones, e.g. a cylinder
+    /// that wraps around horizontally between dead top and bottom edges
+    pub fn set_boundaries(&mut self, vertical: Boundary, horizontal: Boundary) {
+        self.vertical = vertical;
+        self.horizontal = horizontal;
+    }
+
+    /// What lies beyond the top and bottom edges,
+    /// and beyond the left and right ones
+    pub fn boundaries(&self) -> (Boundary, Boundary) {
+        (self.vertical, self.horizontal)
+    }
+
+    /// Whether several neighbors can be the same cell because the board
+    /// wraps around an axis that is less than 3 cells long
+    fn tiny_torus(&self) -> bool {
+        (self.vertical == Boundary::Toroidal && self.hgt < 3)
+            || (self.horizontal == Boundary::Toroidal && self.wth < 3)
+    }
+
+    /// Number of lines and columns
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.hgt, self.wth)
+    }
+
+    /// Number of live cells
+    pub fn population(&self) -> usize {
+        self.cnt
+    }
+
+    /// Number of cells born during the last update
+    pub fn last_births(&self) -> usize {
+        self.born
+    }
+
+    /// Number of cells that died during the last update
+    pub fn last_deaths(&self) -> usize {
+        self.dead
+    }
+
+    /// Fraction of the cells that are alive
+    pub fn density(&self) -> f64 {
+        self.cnt as f64 / (self.hgt * self.wth) as f64
+    }
+
+    /// Fraction of the cells that changed state during the last update
+    pub fn activity(&self) -> f64 {
+        (self.born + self.dead) as f64 / (self.hgt * self.wth) as f64
+    }
+
+    /// Whether the cell at line `i` and column `j` is alive.
+    /// Indices wrap around the edges of the canvas.
+    pub fn get(&self, i: usize, j: usize) -> bool {
+        self.curr.get([i % self.hgt, j % self.wth])
+    }
+
+    /// Number of live neighbors of the cell at line `i` and column `j`,
+    /// according to the boundary conditions and to the neighborhood
+    /// in which its current state is counted (see `set_neighborhoods`).
+    /// Indices wrap around the edges of the canvas.
+    pub fn live_neighbors(&self, i: usize, j: usize) -> usize {
+        let (i, j) = (i % self.hgt, j % self.wth);
+        if self.curr.get([i, j]) {
+            self.count_neigh(self.survival_neighborhood(), i, j)
+        } else {
+            self.count_neigh(self.neigh, i, j)
+        }
+    }
+
+    /// State of the cell at line `i` and column `j` after the next call
+    /// to `next`, ignoring noise and edits that are not yet applied
+    pub fn next_state(&self, i: usize, j: usize) -> bool {
+        if self.is_frozen(i, j) {
+            return self.get(i, j);
+        }
+        let n = self.live_neighbors(i, j);
+        if self.get(i, j) {
+            self.rules.s[n]
+        } else {
+            self.rules.b[n]
+        }
+    }
+
+    /// Immediately change the state of the cell at line `i` and column `j`,
+    /// the next call to `next` takes it into account.
+    /// Indices wrap around the edges of the canvas.
+    /// As with `update`, changing the cell drops the history of recent states.
+    pub fn set(&mut self, i: usize, j: usize, alive: bool) {
+        let idx = [i % self.hgt, j % self.wth];
+        if self.curr.get(idx) == alive {
+            return;
+        }
+        if alive {
+            self.cnt += 1;
+        } else {
+            self.cnt -= 1;
+        }
+        self.curr.set(idx, alive);
+        if let Some(ages) = &mut self.ages {
+            ages[idx[0] * self.wth + idx[1]] = 0;
+        }
+        if let Some(activity) = &mut self.activity {
+            activity[idx[0] * self.wth + idx[1]] += 1;
+        }
+        self.history.clear();
+    }
+
+    /// Prevent the cell at line `i` and column `j` from changing state
+    /// in later calls to `next`, or let it follow the rules again.
+    /// A frozen cell is still counted as a neighbor, so live frozen cells
+    /// can be used as walls and obstacles. Indices wrap around the edges
+    /// of the canvas, and the cell can still be changed by `set`.
+    pub fn set_frozen(&mut self, i: usize, j: usize, frozen: bool) {
+        let idx = [i % self.hgt, j % self.wth];
+        let (hgt, wth) = (self.hgt, self.wth);
+        self.frozen
+            .get_or_insert_with(|| Bits::new(hgt, wth))
+            .set(idx, frozen);
+    }
+
+    /// Whether the cell at line `i` and column `j` keeps its state,
+    /// see `set_frozen`. Indices wrap around the edges of the canvas.
+    pub fn is_frozen(&self, i: usize, j: usize) -> bool {
+        self.frozen
+            .as_ref()
+            .is_some_and(|f| f.get([i % self.hgt, j % self.wth]))
+    }
+
+    /// Surround the board with a wall of frozen live cells along all
+    /// four edges, as a petri dish that patterns bounce off or stick to
+    /// instead of leaving the board, whatever the boundary conditions.
+    pub fn init_wall(&mut self) {
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                if i == 0 || j == 0 || i + 1 == self.hgt || j + 1 == self.wth {
+                    self.set(i, j, true);
+                    self.set_frozen(i, j, true);
+                }
+            }
+        }
+    }
+
+    /// Set the state of a cell of a pattern being placed,
+    /// effective after the next `update`.
+    /// Positions outside of the canvas wrap around along axes that do so
+    /// and are dropped otherwise.
+    fn stamp(&mut self, i: isize, j: isize, alive: bool) {
+        let place = |boundary: Boundary, i: isize, n: usize| match boundary {
+            Boundary::Toroidal => Some(i.rem_euclid(n as isize) as usize),
+            Boundary::Dead | Boundary::Reflect => {
+                Some(i as usize).filter(|_| 0 <= i && i < n as isize)
+            }
+        };
+        if let (Some(i), Some(j)) = (
+            place(self.vertical, i, self.hgt),
+            place(self.horizontal, j, self.wth),
+        ) {
+            self.edits.push(([i, j], alive));
+        }
+    }
+
+    /// 2D Array access to a neighbor, according to the boundary conditions.
+    /// `None` if the neighbor is outside of the canvas and does not exist.
+    fn index_move(&self, i: usize, j: usize, mvi: isize, mvj: isize) -> Option<[usize; 2]> {
+        Some([
+            axis_move(self.vertical, i, mvi, self.hgt)?,
+            axis_move(self.horizontal, j, mvj, self.wth)?,
+        ])
+    }
+
+    /// Count live neighbors in `neigh`
+    fn count_neigh(&self, neigh: Neighborhood, i: usize, j: usize) -> usize {
+        if self.tiny_torus() {
+            return self.count_distinct_neigh(neigh, i, j);
+        }
+        let mut res = 0;
+        for &(mvi, mvj) in neigh.offsets(i) {
+            if let Some(idx) = self.index_move(i, j, mvi, mvj) {
+                if self.curr.get(idx) {
+                    res += 1;
+                }
+            }
+        }
+        res
+    }
+
+    /// Same as `count_neigh` for a cell that is not on an edge:
+    /// all neighbors are within the board, no wrapping or clipping is needed.
+    fn count_interior_neigh(&self, neigh: Neighborhood, i: usize, j: usize) -> usize {
+        neigh
+            .offsets(i)
+            .iter()
+            .filter(|&&(mvi, mvj)| {
+                self.curr
+                    .get([(i as isize + mvi) as usize, (j as isize + mvj) as usize])
+            })
+            .count()
+    }
+
+    /// Write to `cols[k]` the number of live cells of column `k - 1`
+    /// on lines `i - 1` to `i + 1`, for the columns next
+    /// to the edges as well (according to the boundary conditions)
+    fn column_sums(&self, i: usize, cols: &mut [usize]) {
+        let lines = [-1, 0, 1].map(|mvi| self.index_move(i, 0, mvi, 0).map(|[i, _]| i));
+        let sum = |j: usize| {
+            lines
+                .iter()
+                .flatten()
+                .filter(|&&i| self.curr.get([i, j]))
+                .count()
+        };
+        for j in 0..self.wth {
+            cols[j + 1] = sum(j);
+        }
+        for k in [0, self.wth + 1] {
+            cols[k] = self
+                .index_move(0, 0, 0, k as isize - 1)
+                .map_or(0, |[_, j]| sum(j));
+        }
+    }
+
+    /// Same as `count_neigh` for a torus less than 3 cells high or wide,
+    /// on which several offsets lead to the same cell (or to the cell itself):
+    /// each distinct neighbor is only counted once.
+    fn count_distinct_neigh(&self, neigh: Neighborhood, i: usize, j: usize) -> usize {
+        let mut seen: Vec<[usize; 2]> = Vec::with_capacity(8);
+        for &(mvi, mvj) in neigh.offsets(i) {
+            if let Some(idx) = self.index_move(i, j, mvi, mvj) {
+                if idx != [i, j] && !seen.contains(&idx) {
+                    seen.push(idx);
+                }
+            }
+        }
+        seen.into_iter().filter(|&idx| self.curr.get(idx)).count()
+    }
+
+    /// Calculate next state of the automaton.
+    /// The next state is written to the back buffer in a single pass
+    /// that also counts births and deaths, then both buffers are swapped.
+    /// Only the cells on the edges need the boundary conditions,
+    /// the others read their neighbors directly.
+    pub fn next(&mut self) {
+        self.step(None);
+    }
+
+    /// Same as `next`, but also returns the cells that changed
+    /// as `(i, j, alive)`, in reading order
+    pub fn next_diff(&mut self) -> Vec<(usize, usize, bool)> {
+        let mut diff = Vec::with_capacity(self.born + self.dead);
+        self.step(Some(&mut diff));
+        diff
+    }
+
+    /// Calculate next state of the automaton,
+    /// recording the cells that changed into `diff` if given
+    fn step(&mut self, mut diff: Option<&mut Vec<(usize, usize, bool)>>) {
+        if self.history.is_empty() {
+            // The state the board evolves from
+            self.remember();
+        }
+        if self.undo_depth > 0 {
+            if self.undo.len() == self.undo_depth {
+                self.undo.pop_front();
+            }
+            self.undo.push_back(self.curr.clone());
+        }
+        self.born = 0;
+        self.dead = 0;
+        self.succ.clear();
+        let survival_neigh = self.survival_neighborhood();
+        let window = self.row_sums
+            && self.neigh == Neighborhood::Moore
+            && survival_neigh == Neighborhood::Moore
+            && !self.tiny_torus();
+        let mut cols = vec![0; if window { self.wth + 2 } else { 0 }];
+        for i in 0..self.hgt {
+            let edge = i == 0 || i + 1 == self.hgt;
+            if window {
+                self.column_sums(i, &mut cols);
+            }
+            for j in 0..self.wth {
+                let was_alive = self.curr.get([i, j]);
+                let counted = if was_alive {
+                    survival_neigh
+                } else {
+                    self.neigh
+                };
+                let neigh = if window {
+                    cols[j] + cols[j + 1] + cols[j + 2] - was_alive as usize
+                } else if edge || j == 0 || j + 1 == self.wth {
+                    self.count_neigh(counted, i, j)
+                } else {
+                    self.count_interior_neigh(counted, i, j)
+                };
+                let mut alive = if was_alive {
+                    self.rules.s[neigh]
+                } else {
+                    self.rules.b[neigh]
+                };
+                if self.frozen.as_ref().is_some_and(|f| f.get([i, j])) {
+                    alive = was_alive;
+                } else if let (true, Some((q, rng))) = (alive, &mut self.noise) {
+                    alive = rng.gen::<f64>() < *q;
+                }
+                match (was_alive, alive) {
+                    (false, true) => self.born += 1,
+                    (true, false) => self.dead += 1,
+                    _ => (),
+                }
+                if was_alive != alive {
+                    if let Some(diff) = &mut diff {
+                        diff.push((i, j, alive));
+                    }
+                    if let Some(activity) = &mut self.activity {
+                        activity[i * self.wth + j] += 1;
+                    }
+                }
+                if alive {
+                    self.succ.set([i, j], true);
+                }
+                if let Some(ages) = &mut self.ages {
+                    let age = &mut ages[i * self.wth + j];
+                    *age = if was_alive && alive { *age + 1 } else { 0 };
+                }
+            }
+        }
+        std::mem::swap(&mut self.curr, &mut self.succ);
+        self.gen += 1;
+        self.finish();
+        self.remember();
+    }
+
+    /// Keep the states before the last `depth` calls to `next`
+    /// so that they can be restored by `step_back` (none by default).
+    /// Each state is a copy of the grid at one bit per cell,
+    /// so this uses `depth` times the memory of the grid.
+    /// Reducing the depth forgets the oldest states.
+    pub fn set_undo_depth(&mut self, depth: usize) {
+        self.undo_depth = depth;
+        while self.undo.len() > depth {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Undo the last call to `next`, if its previous state was kept.
+    /// Returns `false` when there is nothing left to undo.
+    /// Activity counts are rewound, but ages of live cells restart from 0,
+    /// the state of the noise generator is not restored,
+    /// and statistics already written by the logger are kept
+    /// (the generation is recorded again by the next call to `next`).
+    pub fn step_back(&mut self) -> bool {
+        let prev = match self.undo.pop_back() {
+            Some(prev) => prev,
+            None => return false,
+        };
+        if let Some(activity) = &mut self.activity {
+            // The cells that changed during the step being undone
+            for (w, (a, b)) in prev.words.iter().zip(&self.curr.words).enumerate() {
+                let changed = a ^ b;
+                for k in (0..64).filter(|k| changed >> k & 1 == 1) {
+                    let a = &mut activity[w * 64 + k];
+                    *a = a.saturating_sub(1);
+                }
+            }
+        }
+        self.curr = prev;
+        self.cnt = self.curr.count();
+        self.born = 0;
+        self.dead = 0;
+        self.gen = self.gen.saturating_sub(1);
+        self.history.pop_back();
+        if let Some(ages) = &mut self.ages {
+            ages.iter_mut().for_each(|a| *a = 0);
+        }
+        true
+    }
+
+    /// Choose the colors of live and dead cells (green on black by default)
+    pub fn set_colors(&mut self, alive: Color, dead: Color) {
+        self.colors = (alive, dead);
+    }
+
+    /// Color live cells according to their age, fading from `young`
+    /// for newborn cells to `old` for cells that have been alive for
+    /// at least `max_age` generations.
+    /// The color of live cells chosen by `set_colors` is then ignored.
+    /// Ages are counted from the moment this is first called.
+    pub fn set_age_colors(&mut self, young: Color, old: Color, max_age: usize) {
+        self.age_colors = Some((young, old, max_age.max(1)));
+        if self.ages.is_none() {
+            self.ages = Some(vec![0; self.hgt * self.wth]);
+        }
+    }
+
+    /// Current state with one `Cell` per position, for drawing
+    fn canvas(&self) -> Canvas<Cell> {
+        let mut canvas = Canvas::new(self.hgt, self.wth, Cell::new());
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                canvas[[i, j]] = Cell {
+                    alive: self.curr.get([i, j]),
+                    age: self.ages.as_ref().map_or(0, |ages| ages[i * self.wth + j]),
+                    activity: self
+                        .activity
+                        .as_ref()
+                        .map_or(0, |activity| activity[i * self.wth + j]),
+                };
+            }
+        }
+        canvas
+    }
+
+    /// Color of a cell according to the chosen colors
+    fn color_of(&self) -> impl Fn(&Cell) -> Color {
+        let (alive, dead) = self.colors;
+        let age_colors = self.age_colors;
+        move |c| {
+            if !c.is_alive() {
+                dead
+            } else if let Some((young, old, max_age)) = age_colors {
+                gradient(young, old, c.age.min(max_age) as f64 / max_age as f64)
+            } else {
+                alive
+            }
+        }
+    }
+
+    /// Output current state to a file
+    pub fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        let name = cfg.render_with(&self.canvas(), self.color_of())?;
+
+        if self.verbose {
+            cfg.report(&crate::ProgressInfo {
+                frame: name,
+                generation: self.gen,
+                population: self.cnt,
+                births: self.born,
+                deaths: self.dead,
+            });
+        }
+        Ok(())
+    }
+
+    /// Count the births and deaths of each cell from now on,
+    /// to be drawn by `render_heatmap`
+    pub fn track_activity(&mut self) {
+        if self.activity.is_none() {
+            self.activity = Some(vec![0; self.hgt * self.wth]);
+        }
+    }
+
+    /// Output to a file the activity of each cell since `track_activity`
+    /// was called instead of the current state: cells are colored by
+    /// their number of births and deaths on a logarithmic scale,
+    /// from black for cells that never changed to white for the most active.
+    pub fn render_heatmap(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        let max = self
+            .activity
+            .as_ref()
+            .and_then(|activity| activity.iter().max().copied())
+            .unwrap_or(0);
+        let scale = ((max + 1) as f64).ln().max(f64::MIN_POSITIVE);
+        cfg.render_with(&self.canvas(), |c| {
+            heat(((c.activity + 1) as f64).ln() / scale)
+        })?;
+        Ok(())
+    }
+
+    /// Write current state to `out` as raw RGB bytes (one byte per channel,
+    /// line by line), and flush it.
+    /// A sequence of frames can be piped to
+    /// `ffmpeg -f rawvideo -pix_fmt rgb24 -s {wth}x{hgt} -i - ...`
+    pub fn stream_frame<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let (_, _, buf) = self
+            .canvas()
+            .to_rgb_buffer_with(&Style::default(), self.color_of());
+        out.write_all(&buf)?;
+        out.flush()
+    }
+
+    /// Width, height and RGBA pixels (4 bytes per pixel, fully opaque,
+    /// line by line) of the current state, one pixel per cell,
+    /// as expected by an HTML canvas.
+    /// Together with `add_from_str`, `init_rand_seeded` and `next`,
+    /// this needs neither the filesystem nor a system source of randomness.
+    pub fn frame_rgba(&self) -> (usize, usize, Vec<u8>) {
+        self.canvas()
+            .to_rgba_buffer_with(&Style::default(), self.color_of())
+    }
+
+    /// Color of each cell as drawn by `render`, line by line
+    pub fn color_grid(&self) -> Vec<Vec<Color>> {
+        self.canvas().color_grid_with(self.color_of())
+    }
+
+    /// Width, height and RGB pixels of the current state drawn as
+    /// a hexagonal grid (see `Canvas::render_hex`), meant to be used
+    /// with `Neighborhood::Hex`. The space around the hexagons is
+    /// the color of dead cells.
+    pub fn render_hex(&self, radius: usize) -> (usize, usize, Vec<u8>) {
+        self.canvas()
+            .render_hex_with(radius, self.colors.1, self.color_of())
+    }
+
+    /// Output current state to `path` as an SVG image with squares
+    /// of `cell_size` units, for figures that should scale cleanly.
+    /// Only cells that are not the color of dead cells are written.
+    pub fn render_svg(&self, path: &str, cell_size: f64) -> io::Result<()> {
+        self.write_svg(path, cell_size, None)
+    }
+
+    /// Same as `render_svg`, with lines of color `grid` between cells
+    pub fn render_svg_grid(&self, path: &str, cell_size: f64, grid: Color) -> io::Result<()> {
+        self.write_svg(path, cell_size, Some(grid))
+    }
+
+    fn write_svg(&self, path: &str, cell_size: f64, grid: Option<Color>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.canvas()
+            .write_svg_with(&mut out, cell_size, self.colors.1, grid, self.color_of())?;
+        out.flush()
+    }
+
+    /// Current state as text, `#` for live cells and spaces for dead ones,
+    /// to be printed in a terminal
+    pub fn render_ascii(&self) -> String {
+        self.canvas()
+            .to_ascii_with(|c| if c.is_alive() { '#' } else { ' ' })
+    }
+
+    /// Same as `render_ascii` with twice the vertical resolution,
+    /// using Unicode half blocks (see `Canvas::to_half_blocks_with`)
+    pub fn render_half_blocks(&self) -> String {
+        self.canvas().to_half_blocks_with(|c| c.is_alive())
+    }
+
+    /// Append current state to an animated GIF
+    #[cfg(feature = "gif")]
+    pub fn record(&self, rec: &mut crate::recorder::GifRecorder) -> io::Result<()> {
+        rec.push_frame_with(&self.canvas(), self.color_of())
+    }
+}
+
+impl Automaton for LifeLike {
+    fn next(&mut self) {
+        LifeLike::next(self);
+    }
+
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        LifeLike::render(self, cfg)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        LifeLike::dimensions(self)
+    }
+
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.canvas()
+            .to_rgb_buffer_with(&Style::default(), self.color_of())
+    }
+}
+
+/// Settings of a `LifeLike` gathered before it is built, for when
+/// `new` would need too many arguments:
+/// `LifeLike::builder().size(100, 200).rule("B36/S23").density(0.3).build()`
+pub struct LifeLikeBuilder {
+    hgt: usize,
+    wth: usize,
+    rule: String,
+    neigh: Neighborhood,
+    /// Vertical and horizontal
+    boundaries: (Boundary, Boundary),
+    density: Option<f64>,
+    seed: Option<u64>,
+    colors: (Color, Color),
+}
+
+impl LifeLikeBuilder {
+    /// Game of life on a torus with the Moore neighborhood,
+    /// the size has to be set
+    pub fn new() -> Self {
+        Self {
+            hgt: 0,
+            wth: 0,
+            rule: LIFE.to_string(),
+            neigh: Neighborhood::Moore,
+            boundaries: (Boundary::Toroidal, Boundary::Toroidal),
+            density: None,
+            seed: None,
+            colors: (ALIVE, DEAD),
+        }
+    }
+
+    /// Number of lines and columns
+    pub fn size(mut self, hgt: usize, wth: usize) -> Self {
+        self.hgt = hgt;
+        self.wth = wth;
+        self
+    }
+
+    /// Rules in any notation accepted by `Rules::new`
+    pub fn rule(mut self, rule: &str) -> Self {
+        self.rule = rule.to_string();
+        self
+    }
+
+    pub fn neighborhood(mut self, neigh: Neighborhood) -> Self {
+        self.neigh = neigh;
+        self
+    }
+
+    pub fn boundary(mut self, boundary: Boundary) -> Self {
+        self.boundaries = (boundary, boundary);
+        self
+    }
+
+    /// Different boundaries for each axis, as with `set_boundaries`
+    pub fn boundaries(mut self, vertical: Boundary, horizontal: Boundary) -> Self {
+        self.boundaries = (vertical, horizontal);
+        self
+    }
+
+    /// Birth cells at random with probability `p` once built,
+    /// as with `init_rand`
+    pub fn density(mut self, p: f64) -> Self {
+        self.density = Some(p);
+        self
+    }
+
+    /// Make the random cells of `density` reproducible,
+    /// as with `init_rand_seeded`
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Colors of live and dead cells, as with `set_colors`
+    pub fn colors(mut self, alive: Color, dead: Color) -> Self {
+        self.colors = (alive, dead);
+        self
+    }
+
+    /// Check all settings at once and create the automaton.
+    /// Fails for the same reasons as `LifeLike::try_new`.
+    pub fn build(self) -> Result<LifeLike, RuleParseError> {
+        let mut game = LifeLike::try_new(
+            self.hgt,
+            self.wth,
+            &self.rule,
+            self.neigh,
+            self.boundaries.0,
+        )?;
+        game.set_boundaries(self.boundaries.0, self.boundaries.1);
+        game.set_colors(self.colors.0, self.colors.1);
+        match (self.density, self.seed) {
+            (Some(p), Some(seed)) => game.init_rand_seeded(p, seed),
+            (Some(p), None) => game.init_rand(p),
+            (None, _) => (),
+        }
+        Ok(game)
+    }
+}
+
+impl Default for LifeLikeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cell {
+    /// All cells are created dead by default.
+    pub fn new() -> Self {
+        Self {
+            alive: false,
+            age: 0,
+            activity: 0,
+        }
+    }
+
+    pub fn is_alive(self) -> bool {
+        self.alive
+    }
+}
+
+/// Two-dimensional array of booleans stored as a flat bitset,
+/// cell `(i, j)` is bit `i * wth + j`
+#[derive(Clone, PartialEq, Eq)]
+struct Bits {
+    wth: usize,
+    words: Vec<u64>,
+}
+
+impl Bits {
+    /// All bits unset
+    fn new(hgt: usize, wth: usize) -> Self {
+        Self {
+            wth,
+            words: vec![0; (hgt * wth).div_ceil(64)],
+        }
+    }
+
+    fn get(&self, [i, j]: [usize; 2]) -> bool {
+        let k = i * self.wth + j;
+        self.words[k / 64] >> (k % 64) & 1 == 1
+    }
+
+    fn set(&mut self, [i, j]: [usize; 2], b: bool) {
+        let k = i * self.wth + j;
+        if b {
+            self.words[k / 64] |= 1 << (k % 64);
+        } else {
+            self.words[k / 64] &= !(1 << (k % 64));
+        }
+    }
+
+    /// Number of set bits
+    fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Unset all bits
+    fn clear(&mut self) {
+        self.words.iter_mut().for_each(|w| *w = 0);
+    }
+
+    /// Positions of the set bits, in increasing order
+    fn ones(&self) -> impl Iterator<Item = [usize; 2]> + '_ {
+        self.words.iter().enumerate().flat_map(move |(w, &word)| {
+            (0..64)
+                .filter(move |b| word >> b & 1 == 1)
+                .map(move |b| [(w * 64 + b) / self.wth, (w * 64 + b) % self.wth])
+        })
+    }
+}
+
+/// Rules indicate for both possible states and for each possible
+/// number of live neighbors whether or not the cell should be alive for the
+/// next iteration.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rules {
+    /// B: Born; S: Survive
+    b: [bool; 9],
+    s: [bool; 9],
+}
+
+impl Rules {
+    /// Rules are initialized from a str that should be either of the form
+    /// `^([0-9])*-([0-9])*$`, where `$1` (resp. `$2`) is a list of
+    /// (not necessarily ordered nor unique) all neighbor counts
+    /// for which the cell should be born (resp. survive) at the next
+    /// turn, or in the standard notation `^B([0-9]*)/S([0-9]*)$`
+    /// (case-insensitive, sections in any order).
+    ///
+    /// See [Wikipedia](https://en.wikipedia.org/wiki/Life-like_cellular_automaton)
+    /// for a complete explanation
+    ///
+    /// Panics if the rule is malformed, see `try_new` for a fallible version
+    pub fn new(s: &str) -> Self {
+        Self::try_new(s).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Rules that follow `f(alive, n)` for a cell with `n` live neighbors
+    pub fn from_fn<F: Fn(bool, usize) -> bool>(f: F) -> Self {
+        Rules {
+            b: std::array::from_fn(|n| f(false, n)),
+            s: std::array::from_fn(|n| f(true, n)),
+        }
+    }
+
+    /// Same as `new`, but reports malformed rules instead of panicking
+    /// A neighborhood suffix (as in `B2/S34H`) is accepted and ignored.
+    pub fn try_new(s: &str) -> Result<Self, RuleParseError> {
+        let mut r = Rules {
+            b: [false; 9],
+            s: [false; 9],
+        };
+        let (s, suffix) = Neighborhood::split_suffix(s);
+        let neigh = suffix.unwrap_or(Neighborhood::Moore);
+        if s.contains('/') {
+            let v: Vec<_> = s.split('/').collect();
+            if v.len() != 2 {
+                return Err(RuleParseError::MissingSection(s.to_string()));
+            }
+            let mut seen = [false; 2];
+            for part in v {
+                let mut it = part.chars();
+                let (tab, idx) = match it.next() {
+                    Some('B') | Some('b') => (&mut r.b, 0),
+                    Some('S') | Some('s') => (&mut r.s, 1),
+                    _ => return Err(RuleParseError::MissingSection(s.to_string())),
+                };
+                seen[idx] = true;
+                Self::read_counts(tab, it, neigh)?;
+            }
+            if seen != [true, true] {
+                return Err(RuleParseError::MissingSection(s.to_string()));
+            }
+        } else if s.contains('-') {
+            let v: Vec<_> = s.split('-').collect();
+            if v.len() != 2 {
+                return Err(RuleParseError::MissingSection(s.to_string()));
+            }
+            Self::read_counts(&mut r.b, v[0].chars(), neigh)?;
+            Self::read_counts(&mut r.s, v[1].chars(), neigh)?;
+        } else {
+            return Err(RuleParseError::MissingSection(s.to_string()));
+        }
+        Ok(r)
+    }
+
+    /// Check that no neighbor count is larger than the size of `neigh`
+    pub fn validate(&self, neigh: Neighborhood) -> Result<(), RuleParseError> {
+        self.validate_each(neigh, neigh)
+    }
+
+    /// Check that no birth count is larger than the size of `birth`
+    /// and no survival count is larger than the size of `survival`
+    pub fn validate_each(
+        &self,
+        birth: Neighborhood,
+        survival: Neighborhood,
+    ) -> Result<(), RuleParseError> {
+        for (tab, neigh) in [(&self.b, birth), (&self.s, survival)] {
+            if let Some(n) = (neigh.size() + 1..9).find(|&n| tab[n]) {
+                return Err(RuleParseError::OutOfRange(n, neigh));
+            }
+        }
+        Ok(())
+    }
+
+    /// Common name of well-known rules (`"HighLife"` for `B36/S23`),
+    /// `None` for the others
+    pub fn name(&self) -> Option<&'static str> {
+        NAMES
+            .iter()
+            .find(|(rules, _)| Rules::new(rules) == *self)
+            .map(|&(_, name)| name)
+    }
+
+    /// Rules followed by the complement of a board that follows these rules
+    /// on the Moore neighborhood: a dead cell with `n` live neighbors
+    /// is born if a live cell with `8 - n` live neighbors would die,
+    /// and conversely. For example `B3/S23` becomes `B0123478/S01234678`.
+    pub fn dual(&self) -> Rules {
+        Rules {
+            b: std::array::from_fn(|n| !self.s[8 - n]),
+            s: std::array::from_fn(|n| !self.b[8 - n]),
+        }
+    }
+
+    /// Whether inverting the states of all cells commutes with the rules,
+    /// as in Day & Night
+    pub fn is_self_dual(&self) -> bool {
+        self.dual() == *self
+    }
+
+    /// Copy of the rules in which each birth and survival count
+    /// is toggled with probability `rate`, e.g. for a genetic search.
+    /// All counts up to 8 may change, so the result should be checked
+    /// with `validate` for neighborhoods with fewer cells.
+    pub fn mutate<R: Rng>(&self, rng: &mut R, rate: f64) -> Rules {
+        let mut res = *self;
+        for x in res.b.iter_mut().chain(res.s.iter_mut()) {
+            if rng.gen::<f64>() < rate {
+                *x = !*x;
+            }
+        }
+        res
+    }
+
+    /// Rules in which each birth and survival count is taken
+    /// from either `self` or `other` with equal probability
+    pub fn crossover<R: Rng>(&self, other: &Rules, rng: &mut R) -> Rules {
+        let mut res = *self;
+        let pairs = res.b.iter_mut().zip(&other.b);
+        for (x, &y) in pairs.chain(res.s.iter_mut().zip(&other.s)) {
+            if rng.gen::<bool>() {
+                *x = y;
+            }
+        }
+        res
+    }
+
+    /// Standard `B3/S23` notation
+    pub fn notation(&self) -> String {
+        self.to_string()
+    }
+
+    /// Whether a dead cell with `n` live neighbors is born
+    pub fn born(&self, n: usize) -> bool {
+        self.b[n]
+    }
+
+    /// Whether a live cell with `n` live neighbors survives
+    pub fn survives(&self, n: usize) -> bool {
+        self.s[n]
+    }
+
+    /// Mark as active all neighbor counts listed as digits,
+    /// none of which can exceed the size of `neigh`
+    fn read_counts(
+        tab: &mut [bool; 9],
+        digits: impl Iterator<Item = char>,
+        neigh: Neighborhood,
+    ) -> Result<(), RuleParseError> {
+        for c in digits {
+            match c.to_digit(10) {
+                Some(n) if n as usize > neigh.size() => {
+                    return Err(RuleParseError::OutOfRange(n as usize, neigh))
+                }
+                Some(n) => tab[n as usize] = true,
+                None => return Err(RuleParseError::InvalidChar(c)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Display in the standard `B3/S23` notation
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B")?;
+        for n in 0..9 {
+            if self.b[n] {
+                write!(f, "{}", n)?;
+            }
+        }
+        write!(f, "/S")?;
+        for n in 0..9 {
+            if self.s[n] {
+                write!(f, "{}", n)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Which cells count as neighbors
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Neighborhood {
+    /// The 8 surrounding cells
+    Moore,
+    /// Only the 4 orthogonally adjacent cells
+    VonNeumann,
+    /// The 6 surrounding cells of a hexagonal grid, stored with odd lines
+    /// shifted half a cell to the right.
+    /// On a torus, the height should be even for the layout to stay
+    /// consistent across the edge.
+    Hex,
+}
+
+impl Neighborhood {
+    /// Relative positions of the neighbors of a cell on line `i`
+    pub(crate) fn offsets(self, i: usize) -> &'static [(isize, isize)] {
+        match self {
+            Neighborhood::Moore => &[
+                (-1, 0),
+                (-1, -1),
+                (-1, 1),
+                (1, 0),
+                (1, -1),
+                (1, 1),
+                (0, -1),
+                (0, 1),
+            ],
+            Neighborhood::VonNeumann => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
+            Neighborhood::Hex if i % 2 == 1 => &[(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)],
+            Neighborhood::Hex => &[(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)],
+        }
+    }
+
+    /// Maximum number of live neighbors
+    pub fn size(self) -> usize {
+        self.offsets(0).len()
+    }
+
+    /// Split a rule string such as `B2/S34H` into the rules and the
+    /// neighborhood given by its suffix (`H` for hexagonal, `V` for von Neumann)
+    fn split_suffix(s: &str) -> (&str, Option<Self>) {
+        let s = s.trim();
+        match s.chars().last() {
+            Some('H') | Some('h') => (&s[..s.len() - 1], Some(Neighborhood::Hex)),
+            Some('V') | Some('v') => (&s[..s.len() - 1], Some(Neighborhood::VonNeumann)),
+            _ => (s, None),
+        }
+    }
+}
+
+/// What lies beyond the edges of the canvas
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Boundary {
+    /// Opposite edges are glued together
+    Toroidal,
+    /// Cells outside of the canvas are permanently dead
+    Dead,
+    /// Cells outside of the canvas mirror the nearest cell on the edge
+    Reflect,
+}
+
+/// Position along an axis of length `n` at offset `mv` from `i`,
+/// according to what lies beyond its ends
+fn axis_move(boundary: Boundary, i: usize, mv: isize, n: usize) -> Option<usize> {
+    let i = i as isize + mv;
+    match boundary {
+        Boundary::Toroidal => Some(i.rem_euclid(n as isize) as usize),
+        Boundary::Dead => Some(i as usize).filter(|_| 0 <= i && i < n as isize),
+        Boundary::Reflect => Some(i.clamp(0, n as isize - 1) as usize),
+    }
+}
+
+/// How patterns that overrun the canvas are placed
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Placement {
+    /// Cells outside of the canvas wrap around on a torus
+    /// and are dropped otherwise
+    Lenient,
+    /// Refuse to place the pattern at all
+    /// (RLE patterns are always placed this way)
+    Strict,
+}
+
+/// Reaction to an RLE pattern that declares different rules than those
+/// of the simulation
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RuleMismatch {
+    /// Load the pattern silently
+    Ignore,
+    /// Load the pattern and print a warning
+    Warn,
+    /// Refuse to load the pattern
+    Fail,
+    /// Switch the simulation to the rules of the pattern,
+    /// fails if they can not be used with the current neighborhood
+    Adopt,
+}
+
+/// Reasons why a rule string could not be read or a board could not be built
+#[derive(Debug)]
+pub enum RuleParseError {
+    /// The birth or survival section could not be found
+    MissingSection(String),
+    /// Not a valid neighbor count
+    InvalidChar(char),
+    /// Not a valid number of states (for Generations rules)
+    InvalidStates(String),
+    /// Unknown or malformed field (for Larger than Life rules)
+    InvalidField(String),
+    /// Neighbor count that the neighborhood cannot reach
+    OutOfRange(usize, Neighborhood),
+    /// The rules are meant for another neighborhood
+    WrongNeighborhood(Neighborhood),
+    /// A board of dimensions `(hgt, wth)` would have no cells
+    EmptyBoard(usize, usize),
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleParseError::MissingSection(s) => {
+                write!(
+                    f,
+                    "rule `{}` should be of the form B{{}}/S{{}} or {{}}-{{}}",
+                    s
+                )
+            }
+            RuleParseError::InvalidChar(c) => {
+                write!(f, "`{}` is not a valid neighbor count", c.escape_default())
+            }
+            RuleParseError::InvalidStates(s) => {
+                write!(f, "`{}` is not a valid number of states (at least 2)", s)
+            }
+            RuleParseError::InvalidField(s) => write!(f, "`{}` is not a valid rule field", s),
+            RuleParseError::OutOfRange(n, neigh) => write!(
+                f,
+                "{} neighbors is out of range for the {:?} neighborhood (at most {})",
+                n,
+                neigh,
+                neigh.size()
+            ),
+            RuleParseError::WrongNeighborhood(neigh) => {
+                write!(f, "rules are meant for the {:?} neighborhood", neigh)
+            }
+            RuleParseError::EmptyBoard(hgt, wth) => {
+                write!(f, "a board of size {}x{} has no cells", hgt, wth)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuleParseError {}
+
+/// Reasons why a pattern could not be loaded
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be read
+    Io(io::Error),
+    /// The extension is not one of the recognized formats
+    UnknownExtension(String),
+    /// A character that the format does not allow, with its byte offset
+    UnexpectedChar(char, usize),
+    /// The RLE header line is missing or malformed
+    InvalidHeader(String),
+    /// A pattern of dimensions `(hgt, wth)` would overrun the canvas
+    DoesNotFit(usize, usize),
+    /// A saved simulation could not be written or read back
+    InvalidState(String),
+    /// A line of a Life 1.06 file is not a pair of integers
+    InvalidCoordinates(String),
+    /// A line of a macrocell file is not a valid node
+    InvalidNode(String),
+    /// A run of cells or lines of an RLE file, with the byte offset of
+    /// its count, is too long for the pattern or the canvas
+    InvalidRun(usize),
+    /// The pattern declares rules that the simulation does not accept
+    WrongRule(String),
+    /// The item at this index of a list of patterns, with its file name,
+    /// could not be added
+    InItem(usize, String, Box<LoadError>),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "could not access file: {}", e),
+            LoadError::UnknownExtension(ext) => {
+                write!(f, "{} is not recognized as a valid extension", ext)
+            }
+            LoadError::UnexpectedChar(c, k) => write!(
+                f,
+                "unknown character `{}` ({}) at byte {}",
+                c.escape_default(),
+                *c as u32,
+                k
+            ),
+            LoadError::InvalidHeader(line) => write!(f, "invalid RLE header `{}`", line),
+            LoadError::DoesNotFit(hgt, wth) => {
+                write!(
+                    f,
+                    "pattern of size {}x{} does not fit in the canvas",
+                    hgt, wth
+                )
+            }
+            LoadError::InvalidState(e) => write!(f, "invalid saved state: {}", e),
+            LoadError::InvalidCoordinates(line) => {
+                write!(f, "`{}` is not a pair of coordinates", line)
+            }
+            LoadError::InvalidNode(line) => write!(f, "`{}` is not a macrocell node", line),
+            LoadError::InvalidRun(k) => write!(f, "run at byte {} is too long", k),
+            LoadError::WrongRule(rule) => write!(f, "pattern expects rule {}", rule),
+            LoadError::InItem(k, file, e) => write!(f, "item {} ({}): {}", k, file, e),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+/// Everything needed to resume a simulation
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Snapshot {
+    rules: Rules,
+    neigh: Neighborhood,
+    #[serde(default)]
+    survival_neigh: Option<Neighborhood>,
+    /// Beyond the top and bottom edges, and beyond the other edges as well
+    /// if `horizontal` is not set
+    boundary: Boundary,
+    #[serde(default)]
+    horizontal: Option<Boundary>,
+    hgt: usize,
+    wth: usize,
+    /// One string per line, `O` for live cells and `.` for dead ones
+    cells: Vec<String>,
+    /// Positions of the frozen cells
+    #[serde(default)]
+    frozen: Vec<(usize, usize)>,
+    /// Number of generations calculated
+    #[serde(default)]
+    gen: usize,
+    /// Probability of births with noise, see `set_noise`
+    #[serde(default)]
+    noise: Option<f64>,
+    cnt: usize,
+    born: usize,
+    dead: usize,
+}
+
+/// Cursor that places the cells of a pattern on the canvas,
+/// driven by the parser of each file format
+struct PatternBuilder<'a> {
+    game: &'a mut LifeLike,
+    i0: isize,
+    j0: isize,
+    i: isize,
+    j: isize,
+    t: Transform,
+}
+
+impl<'a> PatternBuilder<'a> {
+    /// Start at the origin `(i0, j0)` of the pattern
+    fn new(game: &'a mut LifeLike, i0: isize, j0: isize, t: Transform) -> Self {
+        Self {
+            game,
+            i0,
+            j0,
+            i: i0,
+            j: j0,
+            t,
+        }
+    }
+
+    /// Place a live cell and move to the next one
+    fn birth(&mut self) {
+        self.game.stamp(self.i, self.j, true);
+        self.skip();
+    }
+
+    /// Place a dead cell and move to the next one
+    fn kill(&mut self) {
+        self.game.stamp(self.i, self.j, false);
+        self.skip();
+    }
+
+    /// Place a live cell on line `r`, column `c` of the pattern,
+    /// without moving the cursor
+    fn birth_at(&mut self, r: isize, c: isize) {
+        let (di, dj) = self.t.offset(r, c);
+        self.game.stamp(self.i0 + di, self.j0 + dj, true);
+    }
+
+    /// Move to the next cell without changing the current one
+    fn skip(&mut self) {
+        self.t.next(&mut self.i, &mut self.j);
+    }
+
+    /// Move to the start of the next line of the pattern
+    fn newline(&mut self) {
+        self.t.newline(&mut self.i, &mut self.j, self.i0, self.j0);
+    }
+
+    /// Place a pattern from its origin with `place_plain`, with characters
+    /// decoded as alive, dead, or `None` to leave the cell unchanged
+    fn place_plain(
+        &mut self,
+        data: &str,
+        comment: Option<char>,
+        decode: impl Fn(char) -> Option<Option<bool>>,
+    ) -> Result<(), LoadError> {
+        let game = &mut *self.game;
+        place_plain(
+            data,
+            comment,
+            self.i0,
+            self.j0,
+            self.t,
+            decode,
+            |i, j, state| {
+                if let Some(alive) = state {
+                    game.stamp(i, j, alive);
+                }
+            },
+        )
+    }
+
+    /// Check that a pattern of the given dimensions fits on the canvas
+    fn check_fits(&self, hgt: usize, wth: usize) -> Result<(), LoadError> {
+        self.game.check_fits(hgt, wth, self.i0, self.j0, &self.t)
+    }
+}
+
+/// Lines of a pattern file without their trailing whitespace
+/// (line endings included), each with the byte offset of its start
+fn trimmed_lines(data: &str) -> impl Iterator<Item = (usize, &str)> {
+    data.split('\n').scan(0, |start, line| {
+        let k = *start;
+        *start += line.len() + 1;
+        Some((k, line.trim_end()))
+    })
+}
+
+/// Place a pattern with one character per cell, shared by all automata
+/// that read such files: `decode` gives the state of each character,
+/// or `None` for characters that the format does not allow.
+/// Lines that start with `comment` are not part of the pattern,
+/// and anything after it on other lines is ignored.
+/// `place` receives each state with the position of its cell on the canvas
+/// (not wrapped) once the pattern is placed at `(i0, j0)` under `t`.
+pub fn place_plain<S>(
+    data: &str,
+    comment: Option<char>,
+    i0: isize,
+    j0: isize,
+    t: Transform,
+    decode: impl Fn(char) -> Option<S>,
+    mut place: impl FnMut(isize, isize, S),
+) -> Result<(), LoadError> {
+    let (mut i, mut j) = (i0, j0);
+    let mut first = true;
+    for (k0, line) in trimmed_lines(data) {
+        if comment.is_some_and(|c| line.starts_with(c)) {
+            continue;
+        }
+        if !first {
+            t.newline(&mut i, &mut j, i0, j0);
+        }
+        first = false;
+        for (k, c) in line.char_indices() {
+            if Some(c) == comment {
+                break;
+            }
+            match decode(c) {
+                Some(state) => place(i, j, state),
+                None => return Err(LoadError::UnexpectedChar(c, k0 + k)),
+            }
+            t.next(&mut i, &mut j);
+        }
+    }
+    Ok(())
+}
+
+/// Plain text: `x` for live cells, `.` for dead ones, and ` ` to leave
+/// a cell unchanged
+fn parse_txt(data: &str, builder: &mut PatternBuilder) -> Result<(), LoadError> {
+    builder.place_plain(data, None, |c| match c {
+        'x' => Some(Some(true)),
+        '.' => Some(Some(false)),
+        ' ' => Some(None),
+        _ => None,
+    })
+}
+
+/// One character per cell: `alive` for live cells and `.` for dead ones,
+/// anything after `comment` is ignored (`*.lif` and `*.cells`)
+fn parse_plain(
+    data: &str,
+    builder: &mut PatternBuilder,
+    comment: char,
+    alive: char,
+) -> Result<(), LoadError> {
+    builder.place_plain(data, Some(comment), |c| match c {
+        c if c == alive => Some(Some(true)),
+        '.' => Some(Some(false)),
+        _ => None,
+    })
+}
+
+/// Life 1.06: one `x y` pair of coordinates per live cell,
+/// line `y` and column `x` of the pattern are relative to its origin
+/// and can be negative.
+/// As for other formats, positions outside of the canvas wrap around
+/// on a torus and are dropped otherwise.
+fn parse_life106(data: &str, builder: &mut PatternBuilder) -> Result<(), LoadError> {
+    for (x, y) in life106_cells(data)? {
+        builder.birth_at(y, x);
+    }
+    Ok(())
+}
+
+/// Macrocell: the quadtree saved by Golly for large patterns,
+/// placed with the origin at the center of the root square.
+/// The rule given by `#R` is checked against the one of the canvas.
+/// Patterns larger than the canvas are rejected rather than wrapped.
+fn parse_macrocell(data: &str, builder: &mut PatternBuilder, name: &str) -> Result<(), LoadError> {
+    if let Some(rule) = macrocell_rule(data) {
+        builder.game.check_rule(rule, name)?;
+    }
+    let (hgt, wth) = builder.game.dimensions();
+    for (x, y) in macrocell_cells(data, hgt.max(wth))? {
+        builder.birth_at(y, x);
+    }
+    Ok(())
+}
+
+/// Run-length encoded: `o` for live cells, `b` for dead ones and `$` for
+/// new lines, each optionally preceded by a number of repetitions.
+/// The rule given by the header is checked against the one of the canvas,
+/// `name` identifies the pattern in warnings.
+/// Runs cannot extend past the dimensions given by the header,
+/// or past the largest dimension of the canvas if there is none.
+fn parse_rle(data: &str, builder: &mut PatternBuilder, name: &str) -> Result<(), LoadError> {
+    let (hgt, wth) = builder.game.dimensions();
+    let mut bounds = (hgt.max(wth), hgt.max(wth));
+    // Position in the pattern, and count of the current run with its offset
+    let (mut r, mut c) = (0, 0);
+    let mut cnt: Option<(usize, usize)> = None;
+    for (k0, line) in trimmed_lines(data) {
+        for (k, ch) in line.char_indices() {
+            let mut run = || cnt.take().unwrap_or((1, k0 + k));
+            match ch {
+                '#' => break,
+                'x' => {
+                    // 'x' marks the start of an 'x = {}, y = {}, rule = {}' header
+                    let header = RleHeader::parse(&line[k..])?;
+                    builder.check_fits(header.hgt, header.wth)?;
+                    bounds = (header.hgt, header.wth);
+                    if let Some(rule) = header.rule {
+                        builder.game.check_rule(&rule, name)?;
+                    }
+                    break;
+                }
+                '$' => {
+                    let (n, start) = run();
+                    if n > bounds.0.saturating_sub(r) {
+                        return Err(LoadError::InvalidRun(start));
+                    }
+                    r += n;
+                    c = 0;
+                    for _ in 0..n {
+                        builder.newline();
+                    }
+                }
+                'o' | 'b' => {
+                    let (n, start) = run();
+                    if r >= bounds.0 || n > bounds.1.saturating_sub(c) {
+                        return Err(LoadError::InvalidRun(start));
+                    }
+                    c += n;
+                    for _ in 0..n {
+                        if ch == 'o' {
+                            builder.birth();
+                        } else {
+                            builder.kill();
+                        }
+                    }
+                }
+                d @ '0'..='9' => {
+                    let (n, start) = cnt.unwrap_or((0, k0 + k));
+                    let n = n
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(d.to_digit(10).unwrap() as usize))
+                        .ok_or(LoadError::InvalidRun(start))?;
+                    cnt = Some((n, start));
                 }
+                '!' => return Ok(()),
+                ' ' | '\t' => (),
+                c => return Err(LoadError::UnexpectedChar(c, k0 + k)),
             }
-            0 => (),
-            _ => panic!("({}, {}) is not a neighbor: abs({}) > 1", mvi, mvj, mvi),
         }
-        match mvj {
-            -1 => {
-                if j == 0 {
-                    j = self.wth - 1;
-                } else {
-                    j -= 1;
-                }
+    }
+    Ok(())
+}
+
+/// Information carried by the `x = {}, y = {}, rule = {}` line of an RLE file
+struct RleHeader {
+    hgt: usize,
+    wth: usize,
+    rule: Option<String>,
+}
+
+impl RleHeader {
+    /// Find and parse the header of the contents of an RLE file
+    fn find(data: &str) -> Result<Self, LoadError> {
+        for line in data.lines() {
+            let line = line.trim_start();
+            if line.starts_with('x') {
+                return Self::parse(line);
             }
-            1 => {
-                if j == self.wth - 1 {
-                    j = 0
-                } else {
-                    j += 1;
-                }
+            if !line.starts_with('#') && !line.is_empty() {
+                break;
             }
-            0 => (),
-            _ => panic!("({}, {}) is not a neighbor: abs({}) > 1", mvi, mvj, mvj),
         }
-        [i, j]
+        Err(LoadError::InvalidHeader(String::new()))
     }
 
-    /// Count live neighbor (Moore neighborhood)
-    fn count_neigh(&self, i: usize, j: usize) -> usize {
-        let mut res = 0;
-        if self.field[self.index_move(i, j, -1, 0)].is_alive() {
-            res += 1;
-        }
-        if self.field[self.index_move(i, j, -1, -1)].is_alive() {
-            res += 1;
+    /// Parse a header line, fields are separated by `,` and values by `=`
+    fn parse(line: &str) -> Result<Self, LoadError> {
+        let invalid = || LoadError::InvalidHeader(line.trim().to_string());
+        let mut hgt = None;
+        let mut wth = None;
+        let mut rule = None;
+        for field in line.split(',') {
+            let v: Vec<_> = field.split('=').map(|s| s.trim()).collect();
+            if v.len() != 2 {
+                return Err(invalid());
+            }
+            match v[0] {
+                "x" => wth = Some(v[1].parse().map_err(|_| invalid())?),
+                "y" => hgt = Some(v[1].parse().map_err(|_| invalid())?),
+                "rule" => rule = Some(v[1].to_string()),
+                _ => return Err(invalid()),
+            }
         }
-        if self.field[self.index_move(i, j, -1, 1)].is_alive() {
-            res += 1;
+        match (hgt, wth) {
+            (Some(hgt), Some(wth)) => Ok(Self { hgt, wth, rule }),
+            _ => Err(invalid()),
         }
-        if self.field[self.index_move(i, j, 1, 0)].is_alive() {
-            res += 1;
+    }
+}
+
+/// Read the dimensions `(hgt, wth)` declared in the header of an RLE file
+/// without loading the pattern
+pub fn rle_dimensions(file: &str) -> Result<(usize, usize), LoadError> {
+    let header = RleHeader::find(&read_pattern(file)?.0)?;
+    Ok((header.hgt, header.wth))
+}
+
+/// Dimensions `(hgt, wth)` of the pattern stored in a file.
+/// Read from the header for RLE files, measured for the other formats.
+pub fn pattern_dimensions(file: &str) -> Result<(usize, usize), LoadError> {
+    let (_, _, hgt, wth) = pattern_extent(file)?;
+    Ok((hgt, wth))
+}
+
+/// Position `(r, c)` of the top left corner relative to the origin
+/// and dimensions `(hgt, wth)` of the pattern stored in a file.
+/// Only Life 1.06 and macrocell patterns can extend above or left of their origin.
+fn pattern_extent(file: &str) -> Result<(isize, isize, usize, usize), LoadError> {
+    let (data, ext) = read_pattern(file)?;
+    data_extent(&data, ext)
+}
+
+/// Contents and format extension of a pattern file.
+/// Files ending in `.gz` (e.g. `glider.rle.gz`) are decompressed
+/// and have the format of the extension before it,
+/// this requires the `flate2` feature.
+fn read_pattern(file: &str) -> Result<(String, &str), LoadError> {
+    let mut parts = file.rsplit('.');
+    let ext = parts.next().unwrap();
+    if ext != "gz" {
+        return Ok((std::fs::read_to_string(file)?, ext));
+    }
+    #[cfg(feature = "flate2")]
+    {
+        use std::io::Read;
+        let mut data = String::new();
+        flate2::read::GzDecoder::new(File::open(file)?).read_to_string(&mut data)?;
+        Ok((data, parts.next().unwrap_or("")))
+    }
+    #[cfg(not(feature = "flate2"))]
+    Err(LoadError::UnknownExtension(String::from("gz")))
+}
+
+/// Same as `pattern_extent` for a pattern in the format associated with `ext`
+fn data_extent(data: &str, ext: &str) -> Result<(isize, isize, usize, usize), LoadError> {
+    let comment = match ext {
+        "rle" => {
+            let header = RleHeader::find(data)?;
+            return Ok((0, 0, header.hgt, header.wth));
         }
-        if self.field[self.index_move(i, j, 1, -1)].is_alive() {
-            res += 1;
+        "life" | "l" => return life106_extent(data),
+        "mc" => return macrocell_extent(data),
+        "lif" if data.starts_with(LIFE_106) => return life106_extent(data),
+        "txt" => None,
+        "lif" => Some('#'),
+        "cells" => Some('!'),
+        ext => return Err(LoadError::UnknownExtension(ext.to_string())),
+    };
+    let lines: Vec<_> = trimmed_lines(data)
+        .map(|(_, line)| line)
+        .filter(|line| comment.is_none_or(|c| !line.starts_with(c)))
+        .collect();
+    let hgt = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(0, |i| i + 1);
+    let wth = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    Ok((0, 0, hgt, wth))
+}
+
+/// First line of Life 1.06 files
+const LIFE_106: &str = "#Life 1.06";
+
+/// Coordinates `(x, y)` of the live cells of a Life 1.06 pattern
+fn life106_cells(data: &str) -> Result<Vec<(isize, isize)>, LoadError> {
+    let mut cells = Vec::new();
+    for line in data.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
         }
-        if self.field[self.index_move(i, j, 1, 1)].is_alive() {
-            res += 1;
+        let coords: Vec<_> = line.split_whitespace().map(str::parse).collect();
+        match coords[..] {
+            [Ok(x), Ok(y)] => cells.push((x, y)),
+            _ => return Err(LoadError::InvalidCoordinates(line.to_string())),
         }
-        if self.field[self.index_move(i, j, 0, -1)].is_alive() {
-            res += 1;
+    }
+    Ok(cells)
+}
+
+/// Same as `pattern_extent` for a Life 1.06 pattern
+fn life106_extent(data: &str) -> Result<(isize, isize, usize, usize), LoadError> {
+    Ok(points_extent(&life106_cells(data)?))
+}
+
+/// Same as `pattern_extent` for the pattern made of the cells `(x, y)`
+fn points_extent(cells: &[(isize, isize)]) -> (isize, isize, usize, usize) {
+    if cells.is_empty() {
+        return (0, 0, 0, 0);
+    }
+    let xmin = cells.iter().map(|c| c.0).min().unwrap();
+    let xmax = cells.iter().map(|c| c.0).max().unwrap();
+    let ymin = cells.iter().map(|c| c.1).min().unwrap();
+    let ymax = cells.iter().map(|c| c.1).max().unwrap();
+    (
+        ymin,
+        xmin,
+        (ymax - ymin) as usize + 1,
+        (xmax - xmin) as usize + 1,
+    )
+}
+
+/// Node of a macrocell quadtree
+enum McNode {
+    /// 8x8 square (level 3), one byte per line with column `c` as bit `c`
+    Leaf([u8; 8]),
+    /// Square of side `2^level` made of four children of the level below
+    /// (north-west, north-east, south-west, south-east).
+    /// Children are nodes numbered from 1 in the order of the file,
+    /// 0 being an empty square, or cell states for level 1.
+    Branch(u32, [usize; 4]),
+}
+
+impl McNode {
+    fn level(&self) -> u32 {
+        match self {
+            McNode::Leaf(_) => 3,
+            McNode::Branch(level, _) => *level,
         }
-        if self.field[self.index_move(i, j, 0, 1)].is_alive() {
-            res += 1;
+    }
+}
+
+/// Rule declared by the `#R` line of a macrocell pattern
+fn macrocell_rule(data: &str) -> Option<&str> {
+    data.lines()
+        .find_map(|line| line.trim().strip_prefix("#R"))
+        .map(str::trim)
+}
+
+/// Nodes of a macrocell pattern in the order of the file, the root last
+fn macrocell_nodes(data: &str) -> Result<Vec<McNode>, LoadError> {
+    let mut nodes: Vec<McNode> = Vec::new();
+    for line in data.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(['[', '#']) {
+            continue;
         }
-        res
+        let invalid = || LoadError::InvalidNode(line.to_string());
+        let node = if line.starts_with(['.', '*', '$']) {
+            let mut rows = [0; 8];
+            let (mut r, mut c) = (0, 0);
+            for ch in line.chars() {
+                match ch {
+                    '$' => {
+                        r += 1;
+                        c = 0;
+                    }
+                    '.' | '*' if r < 8 && c < 8 => {
+                        if ch == '*' {
+                            rows[r] |= 1 << c;
+                        }
+                        c += 1;
+                    }
+                    _ => return Err(invalid()),
+                }
+            }
+            McNode::Leaf(rows)
+        } else {
+            let fields: Vec<usize> = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid())?;
+            let (level, children) = match fields[..] {
+                [level, nw, ne, sw, se] if (1..64).contains(&level) => {
+                    (level as u32, [nw, ne, sw, se])
+                }
+                _ => return Err(invalid()),
+            };
+            // Children have to be defined earlier, one level below
+            let valid = level == 1
+                || children
+                    .iter()
+                    .all(|&k| k == 0 || nodes.get(k - 1).is_some_and(|n| n.level() + 1 == level));
+            if !valid {
+                return Err(invalid());
+            }
+            McNode::Branch(level, children)
+        };
+        nodes.push(node);
     }
+    Ok(nodes)
+}
 
-    /// Calculate next state of the automaton
-    pub fn next(&mut self) {
-        for i in 0..self.hgt {
-            for j in 0..self.wth {
-                let neigh = self.count_neigh(i, j);
-                let cell = &mut self.field[[i, j]];
-                if cell.is_alive() {
-                    if !self.rules.s[neigh] {
-                        cell.kill();
+/// Smallest rectangle `(xmin, ymin, xmax, ymax)` that contains the live cells
+/// of each node relative to its top left corner, `None` for empty nodes.
+/// Nodes are shared in the quadtree, so this takes time proportional
+/// to the length of the file rather than to the size of the pattern.
+fn macrocell_bounds(nodes: &[McNode]) -> Vec<Option<[isize; 4]>> {
+    let mut bounds: Vec<Option<[isize; 4]>> = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        let b = match node {
+            McNode::Leaf(rows) => {
+                let ys: Vec<_> = (0..8).filter(|&r| rows[r] != 0).collect();
+                let all = rows.iter().fold(0, |acc, row| acc | row);
+                ys.first().map(|&ymin| {
+                    [
+                        all.trailing_zeros() as isize,
+                        ymin as isize,
+                        7 - all.leading_zeros() as isize,
+                        ys[ys.len() - 1] as isize,
+                    ]
+                })
+            }
+            McNode::Branch(level, children) => {
+                let half = 1isize << (level - 1);
+                let mut b: Option<[isize; 4]> = None;
+                for (q, &child) in children.iter().enumerate() {
+                    let inner = match (*level, child) {
+                        (_, 0) => None,
+                        (1, _) => Some([0; 4]),
+                        (_, k) => bounds[k - 1],
+                    };
+                    if let Some([x0, y0, x1, y1]) = inner {
+                        let (dx, dy) = (half * (q % 2) as isize, half * (q / 2) as isize);
+                        let [x0, y0, x1, y1] = [x0 + dx, y0 + dy, x1 + dx, y1 + dy];
+                        b = Some(match b {
+                            None => [x0, y0, x1, y1],
+                            Some([a0, b0, a1, b1]) => {
+                                [a0.min(x0), b0.min(y0), a1.max(x1), b1.max(y1)]
+                            }
+                        });
                     }
-                } else if self.rules.b[neigh] {
-                    cell.birth();
                 }
+                b
             }
-        }
-        self.update();
+        };
+        bounds.push(b);
     }
+    bounds
+}
 
-    /// Output current state to a file
-    pub fn render(&mut self, cfg: &mut crate::Config) {
-        let name = cfg.frame();
-        self.field.render(&name);
+/// Same as `pattern_extent` for a macrocell pattern
+fn macrocell_extent(data: &str) -> Result<(isize, isize, usize, usize), LoadError> {
+    Ok(nodes_extent(&macrocell_nodes(data)?))
+}
 
-        eprint!(
-            "\rDone generation {} : {} alive (+{} ; -{})",
-            name, self.cnt, self.born, self.dead
-        );
+/// Same as `pattern_extent` for the nodes of a macrocell pattern,
+/// without expanding the quadtree
+fn nodes_extent(nodes: &[McNode]) -> (isize, isize, usize, usize) {
+    let (level, [xmin, ymin, xmax, ymax]) = match (nodes.last(), macrocell_bounds(nodes).last()) {
+        (Some(root), Some(&Some(b))) => (root.level(), b),
+        _ => return (0, 0, 0, 0),
+    };
+    let half = 1isize << (level - 1);
+    (
+        ymin - half,
+        xmin - half,
+        (ymax - ymin) as usize + 1,
+        (xmax - xmin) as usize + 1,
+    )
+}
+
+/// Coordinates `(x, y)` of the live cells of a macrocell pattern.
+/// As in Golly, the origin is at the center of the root square.
+/// Fails without expanding the quadtree if the live cells span
+/// more than `max` lines or columns, since a small file can describe
+/// a pattern far too large to be expanded.
+fn macrocell_cells(data: &str, max: usize) -> Result<Vec<(isize, isize)>, LoadError> {
+    let nodes = macrocell_nodes(data)?;
+    let (_, _, hgt, wth) = nodes_extent(&nodes);
+    if hgt > max || wth > max {
+        return Err(LoadError::DoesNotFit(hgt, wth));
+    }
+    let mut cells = Vec::new();
+    if let Some(root) = nodes.last() {
+        let half = 1isize << (root.level() - 1);
+        expand_macrocell(&nodes, nodes.len(), -half, -half, &mut cells);
     }
+    Ok(cells)
 }
 
-impl Cell {
-    /// All cells are created dead by default.
-    pub fn new() -> Self {
-        Self {
-            /// Changing the state has to be done
-            /// after all cells have been checked
-            curr: false,
-            succ: false,
+/// Push the live cells of node `k` whose top left corner is `(x, y)`
+fn expand_macrocell(
+    nodes: &[McNode],
+    k: usize,
+    x: isize,
+    y: isize,
+    cells: &mut Vec<(isize, isize)>,
+) {
+    if k == 0 {
+        return;
+    }
+    match &nodes[k - 1] {
+        McNode::Leaf(rows) => {
+            for (r, row) in rows.iter().enumerate() {
+                for c in (0..8).filter(|c| row >> c & 1 == 1) {
+                    cells.push((x + c as isize, y + r as isize));
+                }
+            }
+        }
+        McNode::Branch(level, children) => {
+            let half = 1isize << (level - 1);
+            for (q, &child) in children.iter().enumerate() {
+                let (cx, cy) = (x + half * (q % 2) as isize, y + half * (q / 2) as isize);
+                if *level == 1 {
+                    if child != 0 {
+                        cells.push((cx, cy));
+                    }
+                } else {
+                    expand_macrocell(nodes, child, cx, cy, cells);
+                }
+            }
         }
     }
+}
 
-    pub fn birth(&mut self) {
-        self.succ = true;
-    }
+/// Longest period of the objects given an apgcode
+pub const MAX_APG_PERIOD: usize = 1024;
 
-    pub fn kill(&mut self) {
-        self.succ = false;
+/// Coordinates along an axis of `n` cells of an object, made contiguous
+/// if the axis wraps around: the largest gap between occupied coordinates
+/// is the one that is not crossed
+fn unwrap_axis(coords: impl Iterator<Item = usize>, n: usize, boundary: Boundary) -> Vec<i64> {
+    let coords: Vec<_> = coords.map(|c| c as i64).collect();
+    if boundary != Boundary::Toroidal {
+        return coords;
     }
-
-    /// Set current state to calculated next state
-    pub fn update(&mut self, born: &mut usize, dead: &mut usize) {
-        if self.succ {
-            if !self.curr {
-                self.curr = true;
-                *born += 1;
-            }
-        } else if self.curr {
-            self.curr = false;
-            *dead += 1;
+    let mut sorted = coords.clone();
+    sorted.sort_unstable();
+    sorted.dedup();
+    // Gap after each occupied coordinate, the last one across the edge
+    let (mut cut, mut widest) = (sorted[sorted.len() - 1], 0);
+    for (k, &c) in sorted.iter().enumerate() {
+        let next = sorted.get(k + 1).copied().unwrap_or(sorted[0] + n as i64);
+        if next - c > widest {
+            widest = next - c;
+            cut = c;
         }
     }
+    coords
+        .into_iter()
+        .map(|c| if c > cut { c - n as i64 } else { c })
+        .collect()
+}
 
-    pub fn is_alive(self) -> bool {
-        self.curr
+/// Extended Wechsler format of a set of cells, as in apgcodes:
+/// the bounding box is split into strips of 5 lines separated by `z`,
+/// and each column of a strip is a digit in base 32 whose bit `k`
+/// is the cell on the `k`-th line of the strip.
+/// Runs of empty columns are shortened to `0`, `w`, `x` or `y`
+/// followed by the number of columns minus 4, and dropped at the end
+/// of a strip.
+fn wechsler(cells: &mut [(i64, i64)]) -> String {
+    const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let imin = cells.iter().map(|c| c.0).min().unwrap_or(0);
+    let jmin = cells.iter().map(|c| c.1).min().unwrap_or(0);
+    cells.iter_mut().for_each(|c| *c = (c.0 - imin, c.1 - jmin));
+    let hgt = cells.iter().map(|c| c.0 + 1).max().unwrap_or(0) as usize;
+    let wth = cells.iter().map(|c| c.1 + 1).max().unwrap_or(0) as usize;
+    let mut strips = vec![vec![0; wth]; hgt.div_ceil(5)];
+    for &(i, j) in cells.iter() {
+        strips[i as usize / 5][j as usize] |= 1 << (i % 5);
+    }
+    let mut res = String::new();
+    for (k, strip) in strips.iter().enumerate() {
+        if k > 0 {
+            res.push('z');
+        }
+        let mut zeros = 0;
+        for &col in strip {
+            if col == 0 {
+                zeros += 1;
+                continue;
+            }
+            while zeros > 39 {
+                res.push_str("yz");
+                zeros -= 39;
+            }
+            match zeros {
+                0 => (),
+                1 => res.push('0'),
+                2 => res.push('w'),
+                3 => res.push('x'),
+                n => {
+                    res.push('y');
+                    res.push(DIGITS[n - 4] as char);
+                }
+            }
+            zeros = 0;
+            res.push(DIGITS[col] as char);
+        }
     }
+    res
 }
 
-/// Rules indicate for both possible states and for each possible
-/// number of live neighbors whether or not the cell should be alive for the
-/// next iteration.
-#[derive(Clone, Copy)]
-struct Rules {
-    /// B: Born; S: Survive
-    b: [bool; 9],
-    s: [bool; 9],
+/// Well-known patterns that can be placed without a file
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Pattern {
+    /// Still life
+    Block,
+    /// Period 2 oscillator
+    Blinker,
+    /// Period 2 oscillator
+    Toad,
+    /// Period 2 oscillator
+    Beacon,
+    /// Smallest spaceship, travels diagonally
+    Glider,
+    /// Lightweight spaceship (LWSS), travels horizontally
+    Lwss,
+    /// Methuselah that stabilizes after 1103 generations
+    RPentomino,
+    /// Gosper glider gun, emits a glider every 30 generations
+    Gosper,
 }
 
-impl Rules {
-    /// Rules are initialized from a str that should be of the form
-    /// `^([0-9])*-([0-9])*$`, where `$1` (resp. `$2`) is a list of
-    /// (not necessarily ordered nor unique) all neighbor counts
-    /// for which the cell should be born (resp. survive) at the next
-    /// turn.
-    ///
-    /// See [Wikipedia](https://en.wikipedia.org/wiki/Life-like_cellular_automaton)
-    /// for a complete explanation
-    /// (although with a different notation: `^B([0-9]*)/S([0-9]*)$ -> $1-$2`)
-    pub fn new(s: &str) -> Self {
-        let mut r = Rules {
-            b: [false; 9],
-            s: [false; 9],
-        };
-        let v: Vec<_> = s.split('-').collect();
-        let (b, s) = (v[0], v[1]);
-        for c in b.chars() {
-            r.b[c.to_digit(10).unwrap() as usize] = true;
-        }
-        for c in s.chars() {
-            r.s[c.to_digit(10).unwrap() as usize] = true;
+impl Pattern {
+    /// Pattern in the RLE format
+    pub fn rle(self) -> &'static str {
+        match self {
+            Pattern::Block => "x = 2, y = 2\n2o$2o!",
+            Pattern::Blinker => "x = 3, y = 1\n3o!",
+            Pattern::Toad => "x = 4, y = 2\nb3o$3o!",
+            Pattern::Beacon => "x = 4, y = 4\n2o$2o$2b2o$2b2o!",
+            Pattern::Glider => "x = 3, y = 3\nbob$2bo$3o!",
+            Pattern::Lwss => "x = 5, y = 4\nbo2bo$o$o3bo$4o!",
+            Pattern::RPentomino => "x = 3, y = 3\nb2o$2o$bo!",
+            Pattern::Gosper => {
+                "x = 36, y = 9\n\
+                24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$\
+                2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!"
+            }
         }
-        r
     }
 }
 
@@ -431,7 +3255,23 @@ pub const DAYNIGHT: &str = "3678-34678";
 pub const MORLEY: &str = "368-245";
 pub const ANNEAL: &str = "4678-35678";
 
+/// Common names of the rules above
+const NAMES: [(&str, &str); 11] = [
+    (LIFE, "Life"),
+    (REPLICATOR, "Replicator"),
+    (SEEDS, "Seeds"),
+    (NODEATH, "Life without Death"),
+    (LIFE34, "34 Life"),
+    (DIAMOEBA, "Diamoeba"),
+    (X22, "2x2"),
+    (HIGHLIFE, "HighLife"),
+    (DAYNIGHT, "Day & Night"),
+    (MORLEY, "Morley"),
+    (ANNEAL, "Anneal"),
+];
+
 /// Possible rotations of a pattern
+#[derive(Clone, Copy)]
 pub enum Rotate {
     None,
     Left,
@@ -441,12 +3281,53 @@ pub enum Rotate {
 
 /// All transformations of a pattern are a combination of a rotation and
 /// a symmetry
+#[derive(Clone, Copy)]
 pub struct Transform {
     rot: Rotate,
     mirror: bool,
 }
 
 impl Transform {
+    /// Displacement from the origin of the cell on line `r`, column `c`
+    /// of the pattern
+    pub fn offset(&self, r: isize, c: isize) -> (isize, isize) {
+        if self.mirror {
+            match self.rot {
+                Rotate::None => (r, -c),
+                Rotate::Left => (-c, -r),
+                Rotate::Right => (c, r),
+                Rotate::Double => (-r, c),
+            }
+        } else {
+            match self.rot {
+                Rotate::None => (r, c),
+                Rotate::Left => (-c, r),
+                Rotate::Right => (c, -r),
+                Rotate::Double => (-r, -c),
+            }
+        }
+    }
+
+    /// Smallest rectangle `(min_i, min_j, max_i, max_j)` relative to the origin
+    /// that contains all cells of a `hgt x wth` pattern whose top left corner
+    /// is on line `r0`, column `c0`
+    pub fn bounds(
+        &self,
+        r0: isize,
+        c0: isize,
+        hgt: usize,
+        wth: usize,
+    ) -> (isize, isize, isize, isize) {
+        let (r1, c1) = (r0 + hgt as isize - 1, c0 + wth as isize - 1);
+        let corners = [(r0, c0), (r0, c1), (r1, c0), (r1, c1)].map(|(r, c)| self.offset(r, c));
+        (
+            corners.iter().map(|c| c.0).min().unwrap(),
+            corners.iter().map(|c| c.1).min().unwrap(),
+            corners.iter().map(|c| c.0).max().unwrap(),
+            corners.iter().map(|c| c.1).max().unwrap(),
+        )
+    }
+
     /// Calculate index of next cell when staying on the same line
     pub fn next(&self, i: &mut isize, j: &mut isize) {
         if self.mirror {
@@ -542,3 +3423,691 @@ pub const T_DB_SYM: Transform = Transform {
     rot: Rotate::Double,
     mirror: true,
 };
+/// Mirror along the main diagonal (lines become columns)
+pub const T_DIAG: Transform = T_RT_SYM;
+/// Mirror along the anti-diagonal
+pub const T_ANTIDIAG: Transform = T_LT_SYM;
+
+/// The 8 symmetries of the square
+pub const T_ALL: [Transform; 8] = [
+    T_NONE, T_LT, T_RT, T_DB, T_NONE_SYM, T_LT_SYM, T_RT_SYM, T_DB_SYM,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Path of a file in the temporary directory, unique to each test
+    fn temp(name: &str) -> String {
+        let dir = std::env::temp_dir().join("cellular_automata_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name).to_string_lossy().into_owned()
+    }
+
+    fn random_board(hgt: usize, wth: usize, p: f64, seed: u64) -> LifeLike {
+        let mut game = LifeLike::new(hgt, wth, LIFE, Neighborhood::Moore, Boundary::Toroidal);
+        game.set_verbose(false);
+        game.init_rand_seeded(p, seed);
+        game
+    }
+
+    /// Load `file` at the top left corner of an empty copy of `game`
+    /// and return its live cells moved back to where `game` has them
+    fn reload(game: &LifeLike, file: &str) -> Vec<(usize, usize)> {
+        let (hgt, wth) = game.dimensions();
+        let (imin, jmin, _, _) = game.live_bounds().unwrap_or((0, 0, 0, 0));
+        let mut copy = LifeLike::new(hgt, wth, LIFE, Neighborhood::Moore, Boundary::Toroidal);
+        copy.add_from_file(file, imin as isize, jmin as isize, T_NONE)
+            .unwrap();
+        copy.update();
+        copy.live_cells().collect()
+    }
+
+    /// Next state of a board stored one `bool` per cell,
+    /// with the Moore neighborhood and the boundaries `(vertical, horizontal)`
+    fn naive_step(
+        cells: &[Vec<bool>],
+        rules: &Rules,
+        (vertical, horizontal): (Boundary, Boundary),
+    ) -> Vec<Vec<bool>> {
+        let (hgt, wth) = (cells.len(), cells[0].len());
+        let mut succ = vec![vec![false; wth]; hgt];
+        for i in 0..hgt {
+            for j in 0..wth {
+                let mut n = 0;
+                for di in -1..=1 {
+                    for dj in -1..=1 {
+                        if (di, dj) == (0, 0) {
+                            continue;
+                        }
+                        let ni = axis_move(vertical, i, di, hgt);
+                        let nj = axis_move(horizontal, j, dj, wth);
+                        if let (Some(ni), Some(nj)) = (ni, nj) {
+                            n += cells[ni][nj] as usize;
+                        }
+                    }
+                }
+                succ[i][j] = if cells[i][j] {
+                    rules.survives(n)
+                } else {
+                    rules.born(n)
+                };
+            }
+        }
+        succ
+    }
+
+    fn cells_of(game: &LifeLike) -> Vec<Vec<bool>> {
+        let (hgt, wth) = game.dimensions();
+        (0..hgt)
+            .map(|i| (0..wth).map(|j| game.get(i, j)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn bits_match_naive_cells() {
+        let mut rng = StdRng::seed_from_u64(30);
+        for &rule in &[LIFE, HIGHLIFE, DAYNIGHT, SEEDS] {
+            for &boundary in &[Boundary::Toroidal, Boundary::Dead, Boundary::Reflect] {
+                for &(hgt, wth) in &[(17, 23), (8, 8), (3, 65), (40, 64)] {
+                    let mut game = LifeLike::new(hgt, wth, rule, Neighborhood::Moore, boundary);
+                    game.set_verbose(false);
+                    game.init_rand_seeded(0.4, rng.gen());
+                    let rules = Rules::new(rule);
+                    let mut cells = cells_of(&game);
+                    for _ in 0..20 {
+                        game.next();
+                        cells = naive_step(&cells, &rules, (boundary, boundary));
+                        assert_eq!(cells_of(&game), cells, "{} {:?}", rule, boundary);
+                        let alive = cells.iter().flatten().filter(|&&c| c).count();
+                        assert_eq!(game.population(), alive);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn interior_counts_match_wrapping_counts() {
+        let mut rng = StdRng::seed_from_u64(60);
+        let neighs = [
+            Neighborhood::Moore,
+            Neighborhood::VonNeumann,
+            Neighborhood::Hex,
+        ];
+        for &neigh in &neighs {
+            for &boundary in &[Boundary::Toroidal, Boundary::Dead, Boundary::Reflect] {
+                let (hgt, wth) = (16, 21);
+                let mut game = LifeLike::new(hgt, wth, LIFE, neigh, boundary);
+                game.init_rand_seeded(0.5, rng.gen());
+                for i in 0..hgt {
+                    for j in 0..wth {
+                        let expected = neigh
+                            .offsets(i)
+                            .iter()
+                            .filter_map(|&(mvi, mvj)| {
+                                let ni = axis_move(boundary, i, mvi, hgt)?;
+                                let nj = axis_move(boundary, j, mvj, wth)?;
+                                Some(game.get(ni, nj))
+                            })
+                            .filter(|&alive| alive)
+                            .count();
+                        assert_eq!(game.count_neigh(neigh, i, j), expected, "({}, {})", i, j);
+                        let edge = i == 0 || j == 0 || i + 1 == hgt || j + 1 == wth;
+                        if !edge {
+                            assert_eq!(game.count_interior_neigh(neigh, i, j), expected);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn row_sums_match_naive_counts() {
+        let mut rng = StdRng::seed_from_u64(82);
+        for &rule in &[LIFE, DAYNIGHT, REPLICATOR] {
+            for &boundary in &[Boundary::Toroidal, Boundary::Dead, Boundary::Reflect] {
+                for &(hgt, wth) in &[(25, 31), (3, 3), (2, 7), (1, 9), (64, 65)] {
+                    let seed = rng.gen();
+                    let mut naive = LifeLike::new(hgt, wth, rule, Neighborhood::Moore, boundary);
+                    let mut sums = LifeLike::new(hgt, wth, rule, Neighborhood::Moore, boundary);
+                    naive.init_rand_seeded(0.6, seed);
+                    sums.init_rand_seeded(0.6, seed);
+                    sums.use_row_sums(true);
+                    for _ in 0..15 {
+                        naive.next();
+                        sums.next();
+                        assert_eq!(cells_of(&sums), cells_of(&naive), "{:?}", boundary);
+                    }
+                }
+            }
+        }
+    }
+
+    /// `cargo test --release row_sums_benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn row_sums_benchmark() {
+        for &p in &[0.2, 0.5] {
+            for &on in &[false, true] {
+                let mut game = random_board(1000, 1000, p, 82);
+                game.use_row_sums(on);
+                let start = std::time::Instant::now();
+                game.advance(20);
+                println!(
+                    "1000x1000, density {}, row sums {}: {:?} per generation",
+                    p,
+                    on,
+                    start.elapsed() / 20
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn thin_torus_counts_each_neighbor_once() {
+        for &(hgt, wth) in &[(1, 5), (5, 1)] {
+            let mut game = LifeLike::new(hgt, wth, LIFE, Neighborhood::Moore, Boundary::Toroidal);
+            game.set_verbose(false);
+            // A blinker lying along the only line or column
+            let at = |k: usize| if hgt == 1 { (0, k) } else { (k, 0) };
+            for k in 1..4 {
+                let (i, j) = at(k);
+                game.set(i, j, true);
+            }
+            // Cells above and below are the cell itself and its two neighbors
+            let counts: Vec<_> = (0..5)
+                .map(|k| {
+                    let (i, j) = at(k);
+                    game.live_neighbors(i, j)
+                })
+                .collect();
+            assert_eq!(counts, [1, 1, 2, 1, 1]);
+            game.next();
+            let alive: Vec<_> = (0..5).map(|k| game.get(at(k).0, at(k).1)).collect();
+            assert_eq!(alive, [false, false, true, false, false]);
+            assert_eq!(game.population(), 1);
+        }
+    }
+
+    /// Cells moved so that their bounding box starts at `(0, 0)`, sorted
+    fn normalize(cells: impl IntoIterator<Item = (isize, isize)>) -> Vec<(isize, isize)> {
+        let mut cells: Vec<_> = cells.into_iter().collect();
+        let imin = cells.iter().map(|c| c.0).min().unwrap();
+        let jmin = cells.iter().map(|c| c.1).min().unwrap();
+        cells.iter_mut().for_each(|c| *c = (c.0 - imin, c.1 - jmin));
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn all_orientations_of_r_pentomino() {
+        let base = [(0, 1), (0, 2), (1, 0), (1, 1), (2, 1)];
+        // The 8 symmetries of the square, computed independently of `Transform`
+        let images: Vec<_> = (0..8)
+            .map(|sym| {
+                normalize(base.iter().map(|&(i, j)| {
+                    let (i, j) = if sym & 4 != 0 { (j, i) } else { (i, j) };
+                    (
+                        if sym & 1 != 0 { -i } else { i },
+                        if sym & 2 != 0 { -j } else { j },
+                    )
+                }))
+            })
+            .collect();
+        let place = |t: Transform| {
+            let mut game = random_board(12, 12, 0., 0);
+            game.add_pattern(Pattern::RPentomino, 5, 5, t).unwrap();
+            normalize(game.live_cells().map(|(i, j)| (i as isize, j as isize)))
+        };
+        let placed: Vec<_> = T_ALL.iter().map(|&t| place(t)).collect();
+        for (k, cells) in placed.iter().enumerate() {
+            assert!(images.contains(cells), "transform {}: {:?}", k, cells);
+            assert!(
+                !placed[..k].contains(cells),
+                "transform {} is a duplicate",
+                k
+            );
+        }
+        assert_eq!(placed[0], normalize(base.iter().copied()));
+        let transposed = base.iter().map(|&(i, j)| (j, i));
+        assert_eq!(place(T_DIAG), normalize(transposed));
+        let anti = base.iter().map(|&(i, j)| (-j, -i));
+        assert_eq!(place(T_ANTIDIAG), normalize(anti));
+    }
+
+    #[test]
+    fn life106_glider() {
+        let data = "#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n";
+        let glider = [(4, 5), (5, 6), (6, 4), (6, 5), (6, 6)];
+        for &ext in &["life", "l", "lif"] {
+            let mut game = random_board(12, 12, 0., 0);
+            game.add_from_str(data, ext, 5, 5, T_NONE).unwrap();
+            assert_eq!(game.live_cells().collect::<Vec<_>>(), glider, "*.{}", ext);
+        }
+        let mut game = random_board(12, 12, 0., 0);
+        game.add_from_str(data, "life", 0, 0, T_NONE).unwrap();
+        let mut cells: Vec<_> = game.live_cells().collect();
+        cells.sort_unstable();
+        assert_eq!(cells, [(0, 1), (1, 0), (1, 1), (1, 11), (11, 0)]);
+        match game.add_from_str("#Life 1.06\n0 1 2\n", "life", 0, 0, T_NONE) {
+            Err(LoadError::InvalidCoordinates(line)) => assert_eq!(line, "0 1 2"),
+            res => panic!("expected invalid coordinates, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn line_endings_and_trailing_whitespace_are_ignored() {
+        let variants: &[(&str, &[&str])] = &[
+            (
+                "txt",
+                &[
+                    ".x.\n..x\nxxx\n",
+                    ".x.\r\n..x\r\nxxx\r\n",
+                    ".x.\r\n..x\nxxx",
+                    ".x \t\n..x\r\nxxx  \r\n\r\n",
+                    ".x\n..x\nxxx\n\n",
+                ],
+            ),
+            (
+                "cells",
+                &[
+                    "!Name: glider\n.O.\n..O\nOOO\n",
+                    "!Name: glider\r\n.O.\r\n..O\r\nOOO\r\n",
+                    "!Name: glider \r\n.O\n..O\t\r\nOOO",
+                    "!Name: glider\n.O.  \n..O\nOOO\n\r\n\n",
+                ],
+            ),
+            (
+                "rle",
+                &[
+                    "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n",
+                    "x = 3, y = 3, rule = B3/S23\r\nbo$2bo$3o!\r\n",
+                    "#C glider\r\nx = 3, y = 3, rule = B3/S23  \nbo$\r\n2bo$3o!",
+                    "x = 3, y = 3, rule = B3/S23\t\nbo$2b\r\no$3o! \r\n\r\n",
+                ],
+            ),
+        ];
+        let glider = [(2, 3), (3, 4), (4, 2), (4, 3), (4, 4)];
+        for &(ext, datas) in variants {
+            for data in datas.iter() {
+                assert_eq!(data_extent(data, ext).unwrap(), (0, 0, 3, 3), "{:?}", data);
+                let mut game = random_board(8, 8, 0., 0);
+                game.add_from_str(data, ext, 2, 2, T_NONE).unwrap();
+                assert_eq!(game.live_cells().collect::<Vec<_>>(), glider, "{:?}", data);
+            }
+        }
+    }
+
+    #[test]
+    fn glider_wraps_on_one_axis_only() {
+        let mut game = random_board(8, 16, 0., 0);
+        game.set_boundaries(Boundary::Toroidal, Boundary::Dead);
+        game.add_pattern(Pattern::Glider, 4, 6, T_NONE).unwrap();
+        let start: Vec<_> = game.live_cells().collect();
+        let mut cells = cells_of(&game);
+        let rules = game.rules;
+        for gen in 1..=60 {
+            game.next();
+            cells = naive_step(&cells, &rules, (Boundary::Toroidal, Boundary::Dead));
+            assert_eq!(cells_of(&game), cells, "generation {}", gen);
+            if gen == 16 {
+                // Across the bottom edge and back in through the top one
+                let mut moved: Vec<_> = start.iter().map(|&(i, j)| ((i + 4) % 8, j + 4)).collect();
+                moved.sort_unstable();
+                assert_eq!(game.live_cells().collect::<Vec<_>>(), moved);
+            }
+        }
+        // Stopped by the right edge instead of reappearing on the left
+        assert!(game.live_cells().all(|(_, j)| j >= 10));
+        assert!(game.live_cells().any(|(_, j)| j == 15));
+    }
+
+    #[test]
+    fn updates_are_not_generations() {
+        let mut game = random_board(10, 10, 0., 0);
+        game.add_pattern(Pattern::Glider, 2, 2, T_NONE).unwrap();
+        game.update();
+        game.update();
+        assert_eq!(game.detect_period(), None);
+        game.next();
+        assert_eq!(game.detect_period(), None);
+        game.update();
+        assert_eq!(game.detect_period(), None);
+        // Drawing the block bypasses the evolution so far
+        game.clear();
+        game.add_pattern(Pattern::Block, 2, 2, T_NONE).unwrap();
+        assert_eq!(game.detect_period(), None);
+        game.next();
+        assert_eq!(game.detect_period(), Some(1));
+        let mut blinker = random_board(10, 10, 0., 0);
+        blinker.add_pattern(Pattern::Blinker, 2, 2, T_NONE).unwrap();
+        blinker.next();
+        assert_eq!(blinker.detect_period(), None);
+        blinker.update();
+        blinker.next();
+        assert_eq!(blinker.detect_period(), Some(2));
+    }
+
+    #[test]
+    fn set_is_an_edit() {
+        let mut game = random_board(12, 12, 0., 0);
+        game.track_activity();
+        game.add_pattern(Pattern::Blinker, 2, 2, T_NONE).unwrap();
+        game.advance(4);
+        assert_eq!(game.detect_period(), Some(2));
+        let before = game.activity.as_ref().unwrap()[6 * 12 + 6];
+        game.set(6, 6, true);
+        game.set(6, 7, true);
+        assert_eq!(game.detect_period(), None);
+        assert_eq!(game.activity.as_ref().unwrap()[6 * 12 + 6], before + 1);
+        // Setting a cell to its current state changes nothing
+        game.advance(4);
+        let period = game.detect_period();
+        let activity = game.activity.clone();
+        let alive = game.get(0, 0);
+        game.set(0, 0, alive);
+        assert_eq!(game.detect_period(), period);
+        assert_eq!(game.activity, activity);
+    }
+
+    #[test]
+    fn step_back_rewinds_activity() {
+        let mut game = random_board(16, 16, 0.4, 66);
+        game.track_activity();
+        game.set_undo_depth(5);
+        let mut counts = Vec::new();
+        for _ in 0..5 {
+            counts.push(game.activity.clone().unwrap());
+            game.next();
+        }
+        while let Some(expected) = counts.pop() {
+            assert!(game.step_back());
+            assert_eq!(
+                game.activity.as_ref(),
+                Some(&expected),
+                "{} steps back",
+                5 - counts.len()
+            );
+        }
+        assert!(!game.step_back());
+    }
+
+    #[test]
+    fn counts_out_of_range_in_rules() {
+        let out_of_range = |s: &str| match Rules::try_new(s) {
+            Err(RuleParseError::OutOfRange(n, neigh)) => Some((n, neigh)),
+            _ => None,
+        };
+        assert_eq!(out_of_range("B39/S23"), Some((9, Neighborhood::Moore)));
+        assert_eq!(out_of_range("39-23"), Some((9, Neighborhood::Moore)));
+        assert_eq!(out_of_range("B2/S347H"), Some((7, Neighborhood::Hex)));
+        assert_eq!(out_of_range("B5/S1V"), Some((5, Neighborhood::VonNeumann)));
+        assert_eq!(out_of_range("B38/S238"), None);
+        assert!(Rules::try_new("B38/S238").is_ok());
+        assert!(matches!(
+            Rules::try_new("B3a/S23"),
+            Err(RuleParseError::InvalidChar('a'))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn state_round_trip_keeps_generation_and_noise() {
+        let mut game = random_board(12, 20, 0.3, 24);
+        game.set_noise_seeded(0.5, 1);
+        game.advance(7);
+        let file = temp("state_round_trip_keeps_generation_and_noise.json");
+        game.save_state(&file).unwrap();
+        let loaded = LifeLike::load_state(&file).unwrap();
+        assert_eq!(loaded.gen, 7);
+        assert_eq!(loaded.noise.as_ref().map(|&(q, _)| q), Some(0.5));
+        assert_eq!(cells_of(&loaded), cells_of(&game));
+        assert_eq!(loaded.population(), game.population());
+        game.set_noise(1.);
+        game.save_state(&file).unwrap();
+        assert!(LifeLike::load_state(&file).unwrap().noise.is_none());
+    }
+
+    #[test]
+    fn rle_runs_are_bounded() {
+        let mut game = random_board(40, 40, 0., 0);
+        for data in &[
+            "x = 3, y = 1\n99999999999o!",
+            "x = 3, y = 1\n999999999b!",
+            "x = 3, y = 1\n99999999999999999999999b!",
+            "x = 3, y = 2\n2o2b!",
+            "x = 3, y = 2\no3$o!",
+            "2$3o45b!",
+        ] {
+            match game.add_from_str(data, "rle", 0, 0, T_NONE) {
+                Err(LoadError::InvalidRun(_)) => (),
+                res => panic!("{:?}: expected an invalid run, got {:?}", data, res),
+            }
+        }
+        game.add_from_str("x = 3, y = 3\n3o2$3b!", "rle", 0, 0, T_NONE)
+            .unwrap();
+        for &p in &[
+            Pattern::Block,
+            Pattern::Blinker,
+            Pattern::Toad,
+            Pattern::Beacon,
+            Pattern::Glider,
+            Pattern::Lwss,
+            Pattern::RPentomino,
+            Pattern::Gosper,
+        ] {
+            game.add_pattern(p, 1, 1, T_NONE).unwrap();
+        }
+    }
+
+    const MC_GLIDER: &str = "[M2] (golly 4.0)\n#R B3/S23\n.*$..*$***$\n4 1 0 0 0\n";
+
+    #[test]
+    fn macrocell_glider() {
+        assert_eq!(data_extent(MC_GLIDER, "mc").unwrap(), (-8, -8, 3, 3));
+        let mut game = random_board(40, 40, 0., 0);
+        game.add_from_str(MC_GLIDER, "mc", 20, 20, T_NONE).unwrap();
+        assert_eq!(
+            game.live_cells().collect::<Vec<_>>(),
+            [(12, 13), (13, 14), (14, 12), (14, 13), (14, 14)]
+        );
+        assert_eq!(game.apgcode().as_deref(), Some("xq4_153"));
+    }
+
+    #[test]
+    fn macrocell_too_large_is_not_expanded() {
+        // Each level is four copies of the one below, up to 2^30 x 2^30 cells
+        let mut data = String::from(
+            "[M2]\n********$********$********$********$********$********$********$********$\n",
+        );
+        for level in 4..=30 {
+            let k = level - 3;
+            data.push_str(&format!("{} {} {} {} {}\n", level, k, k, k, k));
+        }
+        let mut game = random_board(40, 40, 0., 0);
+        assert_eq!(
+            data_extent(&data, "mc").unwrap(),
+            (-1 << 29, -1 << 29, 1 << 30, 1 << 30)
+        );
+        game.set_placement(Placement::Lenient);
+        match game.add_from_str(&data, "mc", 0, 0, T_NONE) {
+            Err(LoadError::DoesNotFit(hgt, wth)) => assert_eq!((hgt, wth), (1 << 30, 1 << 30)),
+            res => panic!("expected the pattern not to fit, got {:?}", res),
+        }
+        assert_eq!(game.population(), 0);
+    }
+
+    #[test]
+    fn apgcodes_of_known_objects() {
+        let known = [
+            ("2o$2o!", "xs4_33"),
+            ("3o!", "xp2_7"),
+            ("bo$2bo$3o!", "xq4_153"),
+            ("b2o$o2bo$b2o!", "xs6_696"),
+            ("b2o$o2bo$bobo$2bo!", "xs7_2596"),
+            ("2o$obo$bo!", "xs5_253"),
+            ("b2o$o2bo$o2bo$b2o!", "xs8_6996"),
+            ("b3o$3o!", "xp2_7e"),
+            ("2o$obo$b2o!", "xs6_356"),
+            ("bo$obo$bo!", "xs4_252"),
+        ];
+        for &(rle, code) in &known {
+            let mut game = random_board(20, 20, 0., 0);
+            game.add_from_str(rle, "rle", 5, 5, T_NONE).unwrap();
+            assert_eq!(game.apgcode().as_deref(), Some(code), "{}", rle);
+        }
+        // A glider split by both edges of the torus
+        let mut game = random_board(20, 20, 0., 0);
+        for &(i, j) in &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)] {
+            game.set((i + 19) % 20, (j + 19) % 20, true);
+        }
+        assert_eq!(game.apgcode().as_deref(), Some("xq4_153"));
+    }
+
+    #[test]
+    fn rule_mismatch_modes() {
+        let data = "x = 3, y = 1, rule = B36/S23\n3o!";
+        let load = |mode: RuleMismatch| {
+            let mut game = random_board(10, 10, 0., 0);
+            game.set_rule_mismatch(mode);
+            let res = game.add_from_str(data, "rle", 2, 2, T_NONE);
+            (res, *game.rules(), game.population())
+        };
+        let (res, rules, population) = load(RuleMismatch::Fail);
+        assert!(matches!(res, Err(LoadError::WrongRule(ref r)) if r == "B36/S23"));
+        assert_eq!((rules, population), (Rules::new(LIFE), 0));
+        let (res, rules, population) = load(RuleMismatch::Adopt);
+        assert!(res.is_ok());
+        assert_eq!((rules, population), (Rules::new(HIGHLIFE), 3));
+        for &mode in &[RuleMismatch::Ignore, RuleMismatch::Warn] {
+            let (res, rules, population) = load(mode);
+            assert!(res.is_ok());
+            assert_eq!((rules, population), (Rules::new(LIFE), 3), "{:?}", mode);
+        }
+        // Rules that the neighborhood cannot follow are not adopted
+        let mut game = LifeLike::new(10, 10, LIFE, Neighborhood::VonNeumann, Boundary::Toroidal);
+        game.set_rule_mismatch(RuleMismatch::Adopt);
+        assert!(game.add_from_str(data, "rle", 2, 2, T_NONE).is_err());
+    }
+
+    #[test]
+    fn day_and_night_is_self_dual() {
+        assert!(Rules::new(DAYNIGHT).is_self_dual());
+        assert!(!Rules::new(LIFE).is_self_dual());
+        for &rule in &[LIFE, HIGHLIFE, DAYNIGHT, MORLEY, ANNEAL] {
+            let rules = Rules::new(rule);
+            assert_eq!(rules.dual().dual(), rules, "{}", rule);
+        }
+        assert_eq!(Rules::new(LIFE).dual(), Rules::new("B0123478/S01234678"));
+    }
+
+    #[test]
+    fn save_rle_round_trip() {
+        let path = temp("round_trip.rle");
+        for seed in 0..5 {
+            let game = random_board(30, 50, 0.3, seed);
+            game.save_rle(&path, &Rules::new(LIFE)).unwrap();
+            let expected: Vec<_> = game.live_cells().collect();
+            assert_eq!(reload(&game, &path), expected);
+        }
+    }
+
+    #[test]
+    fn save_cells_round_trip() {
+        let path = temp("round_trip.cells");
+        for seed in 0..5 {
+            let game = random_board(30, 50, 0.3, seed);
+            game.save_cells(&path, Some("soup")).unwrap();
+            let expected: Vec<_> = game.live_cells().collect();
+            assert_eq!(reload(&game, &path), expected);
+        }
+    }
+
+    /// The live cells of `game` in each plain text format,
+    /// cropped to their bounding box, with the extension of the format
+    fn encode_all(game: &LifeLike) -> Vec<(String, &'static str)> {
+        let (imin, jmin, imax, jmax) = game.live_bounds().unwrap();
+        let lines = |alive: char, dead: char| -> String {
+            (imin..=imax)
+                .map(|i| {
+                    let line: String = (jmin..=jmax)
+                        .map(|j| if game.get(i, j) { alive } else { dead })
+                        .collect();
+                    line + "\n"
+                })
+                .collect()
+        };
+        let life106: String = game
+            .live_cells()
+            .map(|(i, j)| format!("{} {}\n", j - jmin, i - imin))
+            .collect();
+        // Tests run in parallel, each needs its own file
+        let path = temp(&format!("encode_{:?}.rle", std::thread::current().id()));
+        game.save_rle(&path, &Rules::new(LIFE)).unwrap();
+        let rle = std::fs::read_to_string(&path).unwrap();
+        vec![
+            (lines('x', '.'), "txt"),
+            (format!("#P 0 0\n{}", lines('*', '.')), "lif"),
+            (format!("{}\n{}", LIFE_106, life106), "lif"),
+            (format!("!Name: test\n{}", lines('O', '.')), "cells"),
+            (rle, "rle"),
+        ]
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        for seed in 0..5 {
+            let game = random_board(20, 30, 0.35, seed);
+            let (imin, jmin, _, _) = game.live_bounds().unwrap();
+            let expected: Vec<_> = game.live_cells().collect();
+            for (data, ext) in encode_all(&game) {
+                let mut copy = random_board(20, 30, 0., 0);
+                copy.add_from_str(&data, ext, imin as isize, jmin as isize, T_NONE)
+                    .unwrap();
+                copy.update();
+                let cells: Vec<_> = copy.live_cells().collect();
+                assert_eq!(cells, expected, "{}:\n{}", ext, data);
+            }
+        }
+    }
+
+    #[test]
+    fn every_format_agrees_under_transforms() {
+        let game = random_board(8, 9, 0.5, 40);
+        for (k, &t) in T_ALL.iter().enumerate() {
+            let mut boards = encode_all(&game).into_iter().map(|(data, ext)| {
+                let mut copy = random_board(20, 20, 0., 0);
+                copy.add_from_str(&data, ext, 10, 10, t).unwrap();
+                copy.update();
+                copy.live_cells().collect::<Vec<_>>()
+            });
+            let first = boards.next().unwrap();
+            assert!(!first.is_empty());
+            assert!(boards.all(|b| b == first), "transform {}", k);
+        }
+    }
+
+    #[test]
+    fn save_cells_empty_board() {
+        let path = temp("empty.cells");
+        let game = random_board(10, 10, 0., 0);
+        game.save_cells(&path, None).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "!\n");
+        assert!(reload(&game, &path).is_empty());
+    }
+
+    #[test]
+    fn reflect_corner_counts_mirrored_neighbors() {
+        let mut game = LifeLike::new(5, 5, LIFE, Neighborhood::Moore, Boundary::Reflect);
+        game.set(0, 0, true);
+        // (-1, -1), (-1, 0) and (0, -1) are all mirrored back onto (0, 0)
+        assert_eq!(game.count_neigh(Neighborhood::Moore, 0, 0), 3);
+        assert_eq!(game.count_neigh(Neighborhood::Moore, 0, 1), 2);
+        assert_eq!(game.count_neigh(Neighborhood::Moore, 1, 0), 2);
+        assert_eq!(game.count_neigh(Neighborhood::Moore, 1, 1), 1);
+        assert_eq!(game.count_neigh(Neighborhood::Moore, 4, 4), 0);
+        assert_eq!(game.count_neigh(Neighborhood::VonNeumann, 0, 0), 2);
+    }
+}
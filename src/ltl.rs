@@ -0,0 +1,399 @@
+use rand::Rng;
+use std::io;
+
+use crate::automaton::Automaton;
+use crate::canvas::*;
+use crate::lifelike::RuleParseError;
+
+/// A cell of a Larger than Life automaton is either dead (0), alive (1),
+/// or dying (2 and above) when the rule has more than 2 states
+#[derive(Clone, Copy)]
+struct Cell {
+    curr: usize,
+    /// Changing the state has to be done after all cells have been checked
+    succ: usize,
+    /// Total number of states, required to choose the fading color
+    states: usize,
+}
+
+impl Colorize for Cell {
+    fn color(&self) -> Color {
+        match self.curr {
+            0 => (0, 0, 0),
+            1 => (255, 255, 255),
+            k => {
+                let v = 255 * (self.states - k) / (self.states - 1);
+                (0, 0, v as u8)
+            }
+        }
+    }
+}
+
+/// Shape of the neighborhood of radius `r`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Shape {
+    /// All cells at distance at most `r` along both axes
+    Moore,
+    /// All cells whose Manhattan distance is at most `r`
+    VonNeumann,
+}
+
+/// Set of neighbor counts, as inclusive ranges
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Counts(Vec<(usize, usize)>);
+
+impl Counts {
+    /// Parse comma-separated single values and inclusive ranges,
+    /// e.g. `2,34-45` for 2 and all counts from 34 to 45.
+    /// An empty string is the empty set.
+    pub fn parse(s: &str) -> Result<Self, RuleParseError> {
+        let invalid = || RuleParseError::InvalidField(s.to_string());
+        let mut v = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (lo, hi) = match part.split_once('-') {
+                Some((lo, hi)) => (lo.trim(), hi.trim()),
+                None => (part, part),
+            };
+            match (lo.parse(), hi.parse()) {
+                (Ok(lo), Ok(hi)) if lo <= hi => v.push((lo, hi)),
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(Counts(v))
+    }
+
+    /// Parse the `{lo}..{hi}` range of the standard notation
+    fn parse_dots(s: &str) -> Result<Self, RuleParseError> {
+        let v: Vec<_> = s.split("..").collect();
+        match v.as_slice() {
+            [lo, hi] => match (lo.parse(), hi.parse()) {
+                (Ok(lo), Ok(hi)) if lo <= hi => Ok(Counts(vec![(lo, hi)])),
+                _ => Err(RuleParseError::InvalidField(s.to_string())),
+            },
+            _ => Err(RuleParseError::InvalidField(s.to_string())),
+        }
+    }
+
+    /// Whether `n` is one of the counts
+    pub fn contains(&self, n: usize) -> bool {
+        self.0.iter().any(|&(lo, hi)| lo <= n && n <= hi)
+    }
+}
+
+/// Rules of a Larger than Life automaton: birth and survival occur
+/// when the number of live cells in the neighborhood is one of a set of counts
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LtLRules {
+    radius: usize,
+    states: usize,
+    middle: bool,
+    s: Counts,
+    b: Counts,
+    shape: Shape,
+}
+
+impl LtLRules {
+    /// Parse the standard notation `R{r},C{states},M{0|1},S{lo}..{hi},B{lo}..{hi},N{M|N}`
+    /// (fields in any order, `M` and `N` optional).
+    /// `C0` and `C2` both describe the usual two-state automata,
+    /// `M1` means that the cell itself is counted in its neighborhood.
+    ///
+    /// Sections may instead be separated by `/`, in which case births and
+    /// survivals are lists of values and ranges as in `Counts::parse`:
+    /// `R5/B34-45/S33-57,60` (the other sections are the same).
+    pub fn new(s: &str) -> Result<Self, RuleParseError> {
+        let invalid = |f: &str| RuleParseError::InvalidField(f.to_string());
+        let slashed = s.contains('/');
+        let sep = if slashed { '/' } else { ',' };
+        let counts = |f: &str| {
+            if slashed {
+                Counts::parse(f)
+            } else {
+                Counts::parse_dots(f)
+            }
+        };
+        let mut radius = None;
+        let mut states = 2;
+        let mut middle = true;
+        let mut sv = None;
+        let mut b = None;
+        let mut shape = Shape::Moore;
+        for field in s.trim().split(sep) {
+            let field = field.trim();
+            match field.chars().next() {
+                Some('R') => radius = Some(field[1..].parse().map_err(|_| invalid(field))?),
+                Some('C') => {
+                    states = match field[1..].parse() {
+                        Ok(0) => 2,
+                        Ok(n) if n >= 2 => n,
+                        _ => return Err(RuleParseError::InvalidStates(field.to_string())),
+                    }
+                }
+                Some('M') => {
+                    middle = match &field[1..] {
+                        "0" => false,
+                        "1" => true,
+                        _ => return Err(invalid(field)),
+                    }
+                }
+                Some('S') => sv = Some(counts(&field[1..])?),
+                Some('B') => b = Some(counts(&field[1..])?),
+                Some('N') => {
+                    shape = match &field[1..] {
+                        "M" => Shape::Moore,
+                        "N" => Shape::VonNeumann,
+                        _ => return Err(invalid(field)),
+                    }
+                }
+                _ => return Err(invalid(field)),
+            }
+        }
+        match (radius, sv, b) {
+            (Some(radius), Some(s), Some(b)) if radius > 0 => Ok(Self {
+                radius,
+                states,
+                middle,
+                s,
+                b,
+                shape,
+            }),
+            _ => Err(RuleParseError::MissingSection(s.to_string())),
+        }
+    }
+}
+
+/// A Larger than Life automaton, generalizing life-like automata
+/// to neighborhoods of any radius
+pub struct LtL {
+    rules: LtLRules,
+    /// Offsets of the neighborhood, reduced modulo the dimensions so that
+    /// cells are counted once even when the board is smaller than it
+    offsets: Vec<(isize, isize)>,
+    field: Canvas<Cell>,
+    hgt: usize,
+    wth: usize,
+    cnt: usize,
+    born: usize,
+}
+
+impl LtL {
+    /// Rules are given at the initialization, and cannot be modified.
+    /// See `LtLRules::new` for the notation.
+    /// On boards smaller than the neighborhood, cells that it reaches
+    /// several times by wrapping around are only counted once.
+    pub fn new(hgt: usize, wth: usize, rules: &str) -> Self {
+        let rules = LtLRules::new(rules).unwrap_or_else(|e| panic!("{}", e));
+        Self {
+            field: Canvas::new(hgt, wth, Cell::new(rules.states)),
+            offsets: Self::offsets(&rules, hgt, wth),
+            rules,
+            hgt,
+            wth,
+            cnt: 0,
+            born: 0,
+        }
+    }
+
+    /// Birth cells at random all over the canvas.
+    /// p is the probability for any cell of being born.
+    pub fn init_rand(&mut self, p: f64) {
+        let mut rng = rand::thread_rng();
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                if rng.gen::<f64>() < p {
+                    self.field[[i, j]].birth();
+                }
+            }
+        }
+        self.update();
+    }
+
+    /// Birth cells at random with probability p, but only within an area
+    /// around the center of size given by f
+    /// (proportion of the total dimensions)
+    pub fn init_cluster(&mut self, f: f64, p: f64) {
+        let mut rng = rand::thread_rng();
+        let lo = |n| (n as f64 * (1. - f) / 2.).floor() as usize;
+        let hi = |n| (n as f64 * (1. + f) / 2.).floor() as usize;
+        for i in lo(self.hgt)..hi(self.hgt) {
+            for j in lo(self.wth)..hi(self.wth) {
+                if rng.gen::<f64>() < p {
+                    self.field[[i, j]].birth();
+                }
+            }
+        }
+        self.update();
+    }
+
+    /// Set each cell to its next state and count the newborn cells
+    pub fn update(&mut self) {
+        self.born = 0;
+        self.cnt = 0;
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                let cell = &mut self.field[[i, j]];
+                if cell.succ == 1 && cell.curr != 1 {
+                    self.born += 1;
+                }
+                cell.update();
+                if cell.is_alive() {
+                    self.cnt += 1;
+                }
+            }
+        }
+    }
+
+    /// Distinct offsets of the cells in the neighborhood of `rules`
+    /// on a `hgt` by `wth` torus, each in `[0, hgt) x [0, wth)`
+    fn offsets(rules: &LtLRules, hgt: usize, wth: usize) -> Vec<(isize, isize)> {
+        let r = rules.radius as isize;
+        let (hgt, wth) = (hgt.max(1) as isize, wth.max(1) as isize);
+        let mut res = Vec::new();
+        for mvi in -r..=r {
+            let w = match rules.shape {
+                Shape::Moore => r,
+                Shape::VonNeumann => r - mvi.abs(),
+            };
+            for mvj in -w..=w {
+                let mv = (mvi.rem_euclid(hgt), mvj.rem_euclid(wth));
+                if mv != (0, 0) || rules.middle {
+                    res.push(mv);
+                }
+            }
+        }
+        res.sort_unstable();
+        res.dedup();
+        res
+    }
+
+    /// Count live cells within the neighborhood (with wrapping around edges)
+    fn count_neigh(&self, i: usize, j: usize) -> usize {
+        self.offsets
+            .iter()
+            .filter(|&&(mvi, mvj)| self.field[self.field.index_move(i, j, mvi, mvj)].is_alive())
+            .count()
+    }
+
+    /// Calculate next state of the automaton
+    pub fn next(&mut self) {
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                let neigh = self.count_neigh(i, j);
+                let cell = &mut self.field[[i, j]];
+                match cell.curr {
+                    0 => {
+                        if self.rules.b.contains(neigh) {
+                            cell.birth();
+                        }
+                    }
+                    1 => {
+                        if !self.rules.s.contains(neigh) {
+                            cell.age();
+                        }
+                    }
+                    _ => cell.age(),
+                }
+            }
+        }
+        self.update();
+    }
+
+    /// Output current state to a file
+    pub fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        let name = cfg.render(&self.field)?;
+
+        eprint!(
+            "\rDone generation {} : {} alive (+{})",
+            name, self.cnt, self.born
+        );
+        Ok(())
+    }
+}
+
+impl Automaton for LtL {
+    fn next(&mut self) {
+        LtL::next(self);
+    }
+
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        LtL::render(self, cfg)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        self.field.dimensions()
+    }
+
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.field.to_rgb_buffer()
+    }
+}
+
+impl Cell {
+    /// All cells are created dead by default.
+    pub fn new(states: usize) -> Self {
+        Self {
+            curr: 0,
+            succ: 0,
+            states,
+        }
+    }
+
+    pub fn birth(&mut self) {
+        self.succ = 1;
+    }
+
+    /// Move one step closer to death
+    pub fn age(&mut self) {
+        self.succ = (self.curr + 1) % self.states;
+    }
+
+    /// Set current state to calculated next state
+    pub fn update(&mut self) {
+        self.curr = self.succ;
+    }
+
+    pub fn is_alive(self) -> bool {
+        self.curr == 1
+    }
+}
+
+pub const BOSCO: &str = "R5,C0,M1,S34..58,B34..45,NM";
+pub const MAJORITY: &str = "R4,C0,M1,S41..81,B41..81,NM";
+pub const WAFFLE: &str = "R7,C0,M1,S100..200,B75..170,NM";
+pub const GLOBE: &str = "R8,C0,M0,S163..223,B74..252,NM";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Neighbor counts of every cell of a board with live cells `alive`
+    fn counts(hgt: usize, wth: usize, rules: &str, alive: &[(usize, usize)]) -> Vec<usize> {
+        let mut game = LtL::new(hgt, wth, rules);
+        for &(i, j) in alive {
+            game.field[[i, j]].birth();
+        }
+        game.update();
+        (0..hgt)
+            .flat_map(|i| (0..wth).map(move |j| (i, j)))
+            .map(|(i, j)| game.count_neigh(i, j))
+            .collect()
+    }
+
+    #[test]
+    fn small_boards_count_each_cell_once() {
+        let alive = [(0, 0), (1, 2), (2, 1)];
+        // A radius of 2 covers all of a 3x3 torus, and more
+        assert_eq!(counts(3, 3, "R2,C0,M1,S0..9,B1..9", &alive), [3; 9]);
+        assert_eq!(
+            counts(3, 3, "R2,C0,M0,S0..9,B1..9", &alive),
+            [2, 3, 3, 3, 3, 2, 3, 2, 3]
+        );
+        // Only along the short axis
+        let wide = counts(2, 9, "R1,C0,M1,S0..9,B1..9", &[(0, 4)]);
+        assert_eq!(wide[..9], [0, 0, 0, 1, 1, 1, 0, 0, 0]);
+        assert_eq!(wide[9..], [0, 0, 0, 1, 1, 1, 0, 0, 0]);
+        // Matches the plain count on a board large enough
+        let big = counts(9, 9, "R2,C0,M1,S0..25,B1..25,NN", &[(4, 4), (0, 0)]);
+        assert_eq!(big.iter().sum::<usize>(), 2 * 13);
+    }
+}
@@ -1,35 +1,66 @@
 #![allow(dead_code)]
 
 use std::fs;
+use std::io;
 use std::process::Command;
 
+use canvas::{Canvas, Color, Colorize, Format, Style};
+
+// Not glob imported: its trait would clash with the `Automaton` enum below
+mod automaton;
 mod brain;
 mod canvas;
+mod colored;
+mod elementary;
+mod generations;
+mod isotropic;
 mod lifelike;
+mod ltl;
+mod margolus;
+#[cfg(feature = "gif")]
+mod recorder;
 mod sandpile;
+mod sparse;
+mod stats;
+mod sweep;
 mod turmite;
+mod wireworld;
 
 use brain::*;
+use colored::*;
+use elementary::*;
+use generations::*;
+use isotropic::*;
 use lifelike::*;
+use ltl::*;
+use margolus::*;
 use sandpile::*;
+use sparse::*;
 use turmite::*;
+use wireworld::*;
 
 fn main() {
     let name = String::from("breeder");
     let algo = Automaton::LifeLike(LIFE);
     let mut cfg = Config::new(algo, name, 25);
 
-    cfg.prepare();
-    render(&mut cfg);
+    if let Err(e) = cfg.prepare() {
+        eprintln!("Could not create the directory of the frames: {}", e);
+        return;
+    }
+    // Frames written before a failure are still worth assembling
+    if let Err(e) = render(&mut cfg) {
+        eprintln!("\nStopped early, could not write a frame: {}", e);
+    }
     cfg.build();
 }
 
-fn render(cfg: &mut Config) {
+fn render(cfg: &mut Config) -> io::Result<()> {
     match &cfg.algo {
         Automaton::Sandpile => {
             let mut pile = Sandpile::new(201, 201);
             for _ in 0..1000 {
-                pile.render(cfg);
+                pile.render(cfg)?;
                 pile.add(100, 100, 5);
                 pile.add(110, 110, 10);
                 pile.add(120, 120, 20);
@@ -39,19 +70,71 @@ fn render(cfg: &mut Config) {
             }
         }
         Automaton::LifeLike(rules) => {
-            let mut game = LifeLike::new(1000, 2000, &rules);
-            game.add_from_file("data/patterns/p69060p5h2v0gun.rle", 0, 0, T_NONE);
-            for _ in 0..2000 {
-                game.render(cfg);
+            let mut game =
+                LifeLike::new(1000, 2000, rules, Neighborhood::Moore, Boundary::Toroidal);
+            game.add_from_file("data/patterns/p69060p5h2v0gun.rle", 0, 0, T_NONE)
+                .unwrap();
+            game.run_render(cfg, 2000, 1)?;
+        }
+        Automaton::SparseLife(rules) => {
+            let mut game = SparseLife::new(200, 200, rules, Neighborhood::Moore);
+            for &(i, j) in &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)] {
+                game.set(i, j, true);
+            }
+            // Follow the glider as it travels
+            for gen in 0..2000 {
+                game.set_viewport(gen / 4 - 100, gen / 4 - 100);
+                game.render(cfg)?;
                 game.next();
             }
         }
+        Automaton::ColoredLife(rules, teams) => {
+            let mut game = ColoredLife::new(300, 400, rules, *teams);
+            game.init_cluster(0.5, 0.4, &vec![1.; *teams as usize]);
+            automaton::run(&mut game, cfg, 1000)?;
+        }
+        Automaton::Isotropic(rules) => {
+            let mut game = Isotropic::new(300, 400, rules);
+            game.init_cluster(0.3, 0.4);
+            automaton::run(&mut game, cfg, 1000)?;
+        }
+        Automaton::LtL(rules) => {
+            let mut game = LtL::new(200, 200, rules);
+            game.init_cluster(0.5, 0.5);
+            automaton::run(&mut game, cfg, 500)?;
+        }
+        Automaton::Margolus(rules) => {
+            let mut game = Margolus::new(300, 400, rules);
+            game.init_cluster(0.3, 0.5);
+            automaton::run(&mut game, cfg, 1000)?;
+        }
+        Automaton::Generations(rules) => {
+            let mut game = Generations::new(300, 400, rules);
+            game.init_cluster(0.2, 0.3);
+            automaton::run(&mut game, cfg, 1000)?;
+        }
+        Automaton::Elementary(rule) => {
+            let mut line = Elementary::new(*rule, 801, 400);
+            line.init_single();
+            automaton::run(&mut line, cfg, 400)?;
+        }
         Automaton::Brain => {
             let mut brain = Brain::new(300, 400);
             brain.init_cluster(0.05, 0.3);
-            for _ in 0..5000 {
-                brain.render(cfg);
-                brain.next();
+            automaton::run(&mut brain, cfg, 5000)?;
+        }
+        Automaton::Wireworld => {
+            let mut circuit = Wireworld::new(20, 40);
+            circuit
+                .add_from_file("data/wireworld_clock.txt", 5, 5, T_NONE)
+                .unwrap();
+            automaton::run(&mut circuit, cfg, 500)?;
+        }
+        Automaton::Langton(rule) => {
+            let mut ant = LangtonAnt::new(300, 300, rule);
+            for _ in 0..1000 {
+                ant.multi(100);
+                ant.render(cfg)?;
             }
         }
         Automaton::Turmite(rules) => {
@@ -61,10 +144,11 @@ fn render(cfg: &mut Config) {
             }
             for _ in 0..2000 {
                 mound.multi(50);
-                mound.render(cfg);
+                mound.render(cfg)?;
             }
         }
     }
+    Ok(())
 }
 
 /// Stores all the relevant information needed to initialize an automaton
@@ -73,6 +157,33 @@ pub struct Config<'a> {
     name: String,
     idx: usize,
     framerate: usize,
+    format: Format,
+    style: Style,
+    /// Pixels and file contents of the last frame,
+    /// reused to avoid allocations for every frame
+    rgb: Vec<u8>,
+    out: Vec<u8>,
+    /// Directory of the frames, `.{type}_{name}` if not set
+    output_dir: Option<String>,
+    /// Beginning of the name of each frame
+    prefix: String,
+    /// Number of digits of the frame numbers
+    padding: usize,
+    /// Called after each frame of a `LifeLike`, see `set_progress`
+    progress: Option<Progress<'a>>,
+}
+
+/// Callback that receives the progress of a simulation
+pub type Progress<'a> = Box<dyn FnMut(&ProgressInfo) + 'a>;
+
+/// State of a simulation when one of its frames was written
+pub struct ProgressInfo {
+    /// Filename of the frame
+    pub frame: String,
+    pub generation: usize,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
 }
 
 impl<'a> Config<'a> {
@@ -83,12 +194,90 @@ impl<'a> Config<'a> {
             name,
             idx: 0,
             framerate,
+            format: Format::Ppm,
+            style: Style::default(),
+            rgb: Vec::new(),
+            out: Vec::new(),
+            output_dir: None,
+            prefix: String::from("out-"),
+            padding: 5,
+            progress: Some(Box::new(|info: &ProgressInfo| {
+                eprint!(
+                    "\rDone generation {} : {} alive (+{} ; -{})",
+                    info.frame, info.population, info.births, info.deaths
+                )
+            })),
         }
     }
 
-    /// File directory: `.{type}_{name}/`
+    /// Draw each cell as a `scale x scale` block of pixels (1 by default)
+    pub fn set_scale(&mut self, scale: usize) {
+        self.set_scale_xy(scale, scale);
+    }
+
+    /// Draw each cell as a block `scale_x` pixels wide and `scale_y` pixels high
+    pub fn set_scale_xy(&mut self, scale_x: usize, scale_y: usize) {
+        self.style.scale_x = scale_x;
+        self.style.scale_y = scale_y;
+    }
+
+    /// Draw lines of the given color between cells
+    /// (`canvas::GRID` is a dark gray distinct from dead cells),
+    /// or no lines with `None`. Only visible with a scale larger than 1.
+    pub fn set_grid(&mut self, grid: Option<Color>) {
+        self.style.grid = grid;
+    }
+
+    /// Apply a gamma correction to the colors of the frames
+    /// (1 by default, no correction), see `Style::set_gamma`
+    pub fn set_gamma(&mut self, gamma: f64) {
+        self.style.set_gamma(gamma);
+    }
+
+    /// Choose the image format of the frames (PPM by default)
+    pub fn set_format(&mut self, format: Format) {
+        self.format = format;
+    }
+
+    /// Write the frames to `path` instead of `.{type}_{name}/`.
+    /// The directory is never deleted: only the frames written by this
+    /// simulation are removed once the video is built. Frames that were
+    /// already there with the same prefix end up in the video as well.
+    pub fn set_output_dir(&mut self, path: &str) {
+        self.output_dir = Some(path.to_string());
+    }
+
+    /// Start the name of each frame with `prefix` (`out-` by default)
+    pub fn set_prefix(&mut self, prefix: &str) {
+        self.prefix = prefix.to_string();
+    }
+
+    /// Left-pad frame numbers with zeros up to `width` digits (5 by default),
+    /// so that frames sort correctly by name
+    pub fn set_padding(&mut self, width: usize) {
+        self.padding = width;
+    }
+
+    /// Call `progress` after each frame written by `LifeLike::render`
+    /// instead of printing the progress to stderr, or report nothing
+    /// with `None`
+    pub fn set_progress(&mut self, progress: Option<Progress<'a>>) {
+        self.progress = progress;
+    }
+
+    /// Pass the state of the simulation to the progress callback, if any
+    fn report(&mut self, info: &ProgressInfo) {
+        if let Some(progress) = &mut self.progress {
+            progress(info);
+        }
+    }
+
+    /// File directory: `.{type}_{name}/` unless chosen by `set_output_dir`
     fn dir(&self) -> String {
-        format!(".{}_{}", self.algo.str(), self.name)
+        match &self.output_dir {
+            Some(dir) => dir.clone(),
+            None => format!(".{}_{}", self.algo.str(), self.name),
+        }
     }
 
     /// Final product: `{type}_{name}.avi`
@@ -96,43 +285,77 @@ impl<'a> Config<'a> {
         format!("{}_{}.avi", self.algo.str(), self.name)
     }
 
-    /// Single state filename: `.{type}_{name}/out-{num}.ppm`
+    /// Single state filename: `{dir}/{prefix}{num}.{ext}`
     /// where num is automatically incremented and left-padded with 0s.
+    /// The directory is created by `prepare`.
     fn frame(&mut self) -> String {
         let idx = self.idx;
         self.idx += 1;
-        format!("{}/out-{}.ppm", self.dir(), Self::lpad(idx, 5))
+        self.frame_name(idx)
+    }
+
+    /// Filename of frame number `idx`
+    fn frame_name(&self, idx: usize) -> String {
+        format!(
+            "{}/{}{}.{}",
+            self.dir(),
+            self.prefix,
+            Self::lpad(idx, self.padding),
+            self.format.ext()
+        )
+    }
+
+    /// Write the next frame from the state of a canvas, returns its filename
+    fn render<T: Colorize>(&mut self, canvas: &Canvas<T>) -> io::Result<String> {
+        self.render_with(canvas, |c| c.color())
+    }
+
+    /// Same as `render`, but with colors chosen by `color`
+    fn render_with<T: Colorize, F: Fn(&T) -> Color>(
+        &mut self,
+        canvas: &Canvas<T>,
+        color: F,
+    ) -> io::Result<String> {
+        let name = self.frame();
+        let (rgb, out) = (&mut self.rgb, &mut self.out);
+        canvas.render_with_buffer(&name, self.format, &self.style, color, rgb, out)?;
+        Ok(name)
     }
 
     /// Cleanup directory: remove avi target if it already exists and
     /// remove directory of ppm files in case it was not properly
     /// deleted during the previous execution.
-    pub fn prepare(&self) {
-        let _ = Command::new("rm")
-            .arg(&self.file())
-            .status()
-            .expect("Cleanup aborted");
+    /// The directory of the frames is then created if it does not exist,
+    /// which fails if the path is not writable.
+    /// A directory chosen by `set_output_dir` is never emptied.
+    pub fn prepare(&self) -> io::Result<()> {
         let _ = Command::new("rm")
-            .arg("-r")
-            .arg(&self.dir())
+            .arg(self.file())
             .status()
             .expect("Cleanup aborted");
-        fs::create_dir(self.dir())
-            .unwrap_or_else(|_| panic!("could not create directory {}", self.dir()));
+        if self.output_dir.is_none() {
+            let _ = Command::new("rm")
+                .arg("-r")
+                .arg(self.dir())
+                .status()
+                .expect("Cleanup aborted");
+        }
+        fs::create_dir_all(self.dir())
     }
 
-    /// Concatenate all ppm files into a single video,
-    /// then cleanup temporary files.
+    /// Concatenate all frames into a single video,
+    /// then cleanup temporary files: the whole default directory,
+    /// or only the frames written in a directory chosen by `set_output_dir`.
     pub fn build(&self) {
         eprintln!("All calculations done");
         let _ = Command::new("ffmpeg")
-            .args(&[
+            .args([
                 "-pattern_type",
                 "glob", // find all frames according to glob pattern
                 "-framerate",
                 "25", // 25 FPS
                 "-i",
-                &format!("{}/*.ppm", self.dir()),
+                &format!("{}/{}*.{}", self.dir(), self.prefix, self.format.ext()),
                 "-vf",
                 "scale=1000:-1", // rescale to 1000px (keep aspect ratio)
                 "-sws_flags",
@@ -145,18 +368,24 @@ impl<'a> Config<'a> {
             ])
             .status()
             .unwrap_or_else(|e| panic!("failed to execute process: {}", e));
-        let _ = Command::new("rm")
-            .arg("-r")
-            .arg(&self.dir())
-            .status()
-            .expect("Cleanup aborted");
+        if self.output_dir.is_some() {
+            for idx in 0..self.idx {
+                let _ = fs::remove_file(self.frame_name(idx));
+            }
+        } else {
+            let _ = Command::new("rm")
+                .arg("-r")
+                .arg(self.dir())
+                .status()
+                .expect("Cleanup aborted");
+        }
     }
 
     /// Left-pad usize with zeros
     fn lpad(s: usize, len: usize) -> String {
         let s = format!("{}", s);
         let l = s.len();
-        format!("{}{}", "0".repeat(len - l), s)
+        format!("{}{}", "0".repeat(len.saturating_sub(l)), s)
     }
 }
 
@@ -164,8 +393,17 @@ impl<'a> Config<'a> {
 pub enum Automaton<'a> {
     Sandpile,
     LifeLike(&'a str),
+    SparseLife(&'a str),
+    ColoredLife(&'a str, u8),
+    Isotropic(&'a str),
+    LtL(&'a str),
+    Margolus(&'a str),
+    Generations(&'a str),
+    Elementary(u8),
     Brain,
-    Turmite(Rules<'a>),
+    Wireworld,
+    Langton(&'a str),
+    Turmite(turmite::Rules<'a>),
 }
 
 impl Automaton<'_> {
@@ -174,7 +412,16 @@ impl Automaton<'_> {
         match self {
             Automaton::Sandpile => String::from("sand"),
             Automaton::LifeLike(rules) => format!("life-{}", rules),
+            Automaton::SparseLife(rules) => format!("sparse-{}", rules),
+            Automaton::ColoredLife(rules, teams) => format!("colored{}-{}", teams, rules),
+            Automaton::Isotropic(rules) => format!("int-{}", rules.replace('/', "-")),
+            Automaton::LtL(rules) => format!("ltl-{}", rules.replace(',', "_")),
+            Automaton::Margolus(rules) => format!("margolus-{}", rules.replace([',', ';'], "_")),
+            Automaton::Generations(rules) => format!("gen-{}", rules.replace('/', "-")),
+            Automaton::Elementary(rule) => format!("elem-{}", rule),
             Automaton::Brain => String::from("brain"),
+            Automaton::Wireworld => String::from("wire"),
+            Automaton::Langton(rule) => format!("ant-{}", rule),
             Automaton::Turmite(_) => String::from("turmite"),
         }
     }
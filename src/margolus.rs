@@ -0,0 +1,177 @@
+use rand::Rng;
+use std::io;
+
+use crate::automaton::Automaton;
+use crate::canvas::*;
+use crate::lifelike::RuleParseError;
+
+/// A cell of a block automaton is either dead or alive
+#[derive(Clone, Copy)]
+struct Cell {
+    alive: bool,
+}
+
+impl Colorize for Cell {
+    fn color(&self) -> Color {
+        if self.alive {
+            (255, 255, 255)
+        } else {
+            (0, 0, 0)
+        }
+    }
+}
+
+/// A block cellular automaton on the Margolus neighborhood: the grid is
+/// partitioned into 2x2 blocks, shifted by one cell along both axes every
+/// other generation, and each block is replaced as a whole according to
+/// a transition table.
+/// Blocks wrap around the edges, which requires even dimensions.
+pub struct Margolus {
+    /// New contents of each block, indexed by its current contents.
+    /// The cells of a block are the bits 1 (top left), 2 (top right),
+    /// 4 (bottom left) and 8 (bottom right).
+    table: [u8; 16],
+    field: Canvas<Cell>,
+    hgt: usize,
+    wth: usize,
+    /// Number of calls to `next`, its parity is the offset of the blocks
+    gen: usize,
+    cnt: usize,
+}
+
+impl Margolus {
+    /// Rules are given at the initialization, and cannot be modified.
+    /// See `parse` for the notation.
+    /// Panics if the dimensions are not even.
+    pub fn new(hgt: usize, wth: usize, rules: &str) -> Self {
+        assert!(
+            hgt.is_multiple_of(2) && wth.is_multiple_of(2) && hgt > 0 && wth > 0,
+            "dimensions of a Margolus automaton should be even"
+        );
+        Self {
+            table: Self::parse(rules).unwrap_or_else(|e| panic!("{}", e)),
+            field: Canvas::new(hgt, wth, Cell { alive: false }),
+            hgt,
+            wth,
+            gen: 0,
+            cnt: 0,
+        }
+    }
+
+    /// Margolus rules are written as the 16 entries of the transition table,
+    /// separated by `;` or `,`, optionally preceded by `MS,D` as in Golly
+    /// (e.g. `MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15`).
+    /// Entry `k` is the new contents of a block whose contents are `k`,
+    /// with the top left, top right, bottom left and bottom right cells
+    /// as bits 1, 2, 4 and 8.
+    pub fn parse(s: &str) -> Result<[u8; 16], RuleParseError> {
+        let s = s.trim();
+        let entries = s.strip_prefix("MS,D").unwrap_or(s);
+        let v: Vec<_> = entries.split([';', ',']).map(str::trim).collect();
+        if v.len() != 16 {
+            return Err(RuleParseError::MissingSection(s.to_string()));
+        }
+        let mut table = [0; 16];
+        for (k, e) in v.iter().enumerate() {
+            table[k] = match e.parse() {
+                Ok(n) if n < 16 => n,
+                _ => return Err(RuleParseError::InvalidField(e.to_string())),
+            };
+        }
+        Ok(table)
+    }
+
+    /// Birth cells at random all over the canvas.
+    /// p is the probability for any cell of being born.
+    pub fn init_rand(&mut self, p: f64) {
+        self.init_cluster(1., p);
+    }
+
+    /// Birth cells at random with probability p, but only within an area
+    /// around the center of size given by f
+    /// (proportion of the total dimensions)
+    pub fn init_cluster(&mut self, f: f64, p: f64) {
+        let mut rng = rand::thread_rng();
+        let lo = |n| (n as f64 * (1. - f) / 2.).floor() as usize;
+        let hi = |n| (n as f64 * (1. + f) / 2.).floor() as usize;
+        for i in lo(self.hgt)..hi(self.hgt) {
+            for j in lo(self.wth)..hi(self.wth) {
+                if rng.gen::<f64>() < p {
+                    self.set(i, j, true);
+                }
+            }
+        }
+    }
+
+    /// Set a single cell to be alive or dead
+    pub fn set(&mut self, i: usize, j: usize, alive: bool) {
+        let cell = &mut self.field[[i % self.hgt, j % self.wth]];
+        match (cell.alive, alive) {
+            (false, true) => self.cnt += 1,
+            (true, false) => self.cnt -= 1,
+            _ => (),
+        }
+        cell.alive = alive;
+    }
+
+    /// Number of live cells
+    pub fn population(&self) -> usize {
+        self.cnt
+    }
+
+    /// Calculate next state of the automaton:
+    /// apply the table to every block of the current partition
+    pub fn next(&mut self) {
+        let offset = (self.gen % 2) as isize;
+        for i in (0..self.hgt).step_by(2) {
+            for j in (0..self.wth).step_by(2) {
+                let cells = [(0, 0), (0, 1), (1, 0), (1, 1)]
+                    .map(|(di, dj)| self.field.index_move(i, j, offset + di, offset + dj));
+                let mut block = 0;
+                for (b, &idx) in cells.iter().enumerate() {
+                    if self.field[idx].alive {
+                        block |= 1 << b;
+                    }
+                }
+                let block = self.table[block];
+                for (b, &idx) in cells.iter().enumerate() {
+                    self.set(idx[0], idx[1], block >> b & 1 == 1);
+                }
+            }
+        }
+        self.gen += 1;
+    }
+
+    /// Output current state to a file
+    pub fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        let name = cfg.render(&self.field)?;
+
+        eprint!("\rDone generation {} : {} alive", name, self.cnt);
+        Ok(())
+    }
+}
+
+impl Automaton for Margolus {
+    fn next(&mut self) {
+        Margolus::next(self);
+    }
+
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        Margolus::render(self, cfg)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        self.field.dimensions()
+    }
+
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.field.to_rgb_buffer()
+    }
+}
+
+/// Billiard ball machine: balls move diagonally and bounce off each other
+pub const BBM: &str = "MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15";
+/// Critters: reversible, with gliders emerging from random soups
+pub const CRITTERS: &str = "MS,D15;14;13;3;11;5;6;1;7;9;10;2;12;4;8;0";
+/// Tron: blocks that are all alive or all dead are inverted
+pub const TRON: &str = "MS,D15;1;2;3;4;5;6;7;8;9;10;11;12;13;14;0";
@@ -0,0 +1,115 @@
+use std::fs::File;
+use std::io;
+
+use crate::automaton::Automaton;
+use crate::canvas::*;
+
+/// Accumulates frames into a single animated GIF.
+/// Requires the `gif` feature.
+pub struct GifRecorder {
+    path: String,
+    delay: u16,
+    palette: Vec<Color>,
+    /// Created when the first frame arrives, since it needs the dimensions
+    encoder: Option<gif::Encoder<File>>,
+}
+
+impl GifRecorder {
+    /// `delay` is the time between frames in hundredths of a second.
+    /// All colors are rounded to the nearest color of `palette`
+    /// (at most 256 entries), e.g. `&[(0, 0, 0), (255, 255, 255)]`
+    /// for a life-like automaton.
+    pub fn new(path: &str, delay: u16, palette: &[Color]) -> Self {
+        if palette.is_empty() || palette.len() > 256 {
+            panic!("a GIF palette should have between 1 and 256 colors");
+        }
+        Self {
+            path: path.to_string(),
+            delay,
+            palette: palette.to_vec(),
+            encoder: None,
+        }
+    }
+
+    /// Index in the palette of the closest color
+    fn quantize(&self, (r, g, b): Color) -> u8 {
+        let dist = |&(pr, pg, pb): &Color| {
+            let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2);
+            d(r, pr) + d(g, pg) + d(b, pb)
+        };
+        let mut best = 0;
+        for (k, c) in self.palette.iter().enumerate() {
+            if dist(c) < dist(&self.palette[best]) {
+                best = k;
+            }
+        }
+        best as u8
+    }
+
+    /// Append the current state of the canvas as a new frame.
+    /// All frames should have the same dimensions.
+    pub fn push_frame<T: Colorize>(&mut self, canvas: &Canvas<T>) -> io::Result<()> {
+        self.push_frame_with(canvas, |c| c.color())
+    }
+
+    /// Same as `push_frame`, but with colors chosen by `color`
+    /// instead of the `Colorize` implementation
+    pub fn push_frame_with<T: Colorize, F: Fn(&T) -> Color>(
+        &mut self,
+        canvas: &Canvas<T>,
+        color: F,
+    ) -> io::Result<()> {
+        let (hgt, wth) = canvas.dimensions();
+        let mut pixels = Vec::with_capacity(hgt * wth);
+        for i in 0..hgt {
+            for j in 0..wth {
+                pixels.push(self.quantize(color(&canvas[[i, j]])));
+            }
+        }
+        self.write_frame(wth, hgt, pixels)
+    }
+
+    /// Append the current state of any automaton as a new frame,
+    /// one pixel per cell
+    pub fn push_automaton(&mut self, automaton: &dyn Automaton) -> io::Result<()> {
+        let (wth, hgt, rgb) = automaton.to_rgb_buffer();
+        let pixels = rgb
+            .chunks(3)
+            .map(|p| self.quantize((p[0], p[1], p[2])))
+            .collect();
+        self.write_frame(wth, hgt, pixels)
+    }
+
+    /// Append a frame of palette indices, creating the file first if needed
+    fn write_frame(&mut self, wth: usize, hgt: usize, pixels: Vec<u8>) -> io::Result<()> {
+        if self.encoder.is_none() {
+            let flat: Vec<u8> = self
+                .palette
+                .iter()
+                .flat_map(|&(r, g, b)| vec![r, g, b])
+                .collect();
+            let file = File::create(&self.path)?;
+            let mut encoder =
+                gif::Encoder::new(file, wth as u16, hgt as u16, &flat).map_err(io::Error::other)?;
+            encoder
+                .set_repeat(gif::Repeat::Infinite)
+                .map_err(io::Error::other)?;
+            self.encoder = Some(encoder);
+        }
+        let mut frame = gif::Frame::from_indexed_pixels(wth as u16, hgt as u16, pixels, None);
+        frame.delay = self.delay;
+        self.encoder
+            .as_mut()
+            .expect("encoder is created with the first frame")
+            .write_frame(&frame)
+            .map_err(io::Error::other)
+    }
+
+    /// Write the end of the file
+    pub fn finish(self) -> io::Result<()> {
+        if let Some(encoder) = self.encoder {
+            encoder.into_inner().map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
+use crate::automaton::Automaton;
 use crate::canvas::*;
 use std::collections::VecDeque;
+use std::io;
 
 /// A single pile of grains in the sandpile
 #[derive(Clone, Copy)]
@@ -24,9 +26,9 @@ impl Colorize for Grain {
     fn color(&self) -> Color {
         match self.hgt {
             0 => (0, 0, 0),
-            1 => (13, 4, 0),
-            2 => (25, 9, 0),
-            3 => (25, 20, 0),
+            1 => (133, 41, 0),
+            2 => (255, 92, 0),
+            3 => (255, 204, 0),
             _ => (0, 0, 0),
         }
     }
@@ -56,28 +58,20 @@ impl Sandpile {
     /// Check that no overflow occurs when looking at a neighbor
     fn is_valid_move(&self, i: usize, j: usize, mvi: isize, mvj: isize) -> bool {
         match mvi {
-            -1 => {
-                if i == 0 {
-                    return false;
-                }
+            -1 if i == 0 => {
+                return false;
             }
-            1 => {
-                if i == self.hgt - 1 {
-                    return false;
-                }
+            1 if i == self.hgt - 1 => {
+                return false;
             }
             _ => (),
         }
         match mvj {
-            -1 => {
-                if j == 0 {
-                    return false;
-                }
+            -1 if j == 0 => {
+                return false;
             }
-            1 => {
-                if j == self.wth - 1 {
-                    return false;
-                }
+            1 if j == self.wth - 1 => {
+                return false;
             }
             _ => (),
         }
@@ -168,12 +162,12 @@ impl Sandpile {
     }
 
     /// Print output to file
-    pub fn render(&mut self, cfg: &mut crate::Config) {
-        let name = cfg.frame();
-        self.field.render(&name);
+    pub fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        let name = cfg.render(&self.field)?;
 
         eprint!("\rDone rendering frame {} : workload {}", name, self.cnt);
         self.cnt = 0;
+        Ok(())
     }
 
     /// Conditionally schedule a pile for topple
@@ -185,3 +179,22 @@ impl Sandpile {
         }
     }
 }
+
+impl Automaton for Sandpile {
+    /// Grains only move when piles topple, so a step is a stabilization
+    fn next(&mut self) {
+        Sandpile::stabilize(self);
+    }
+
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        Sandpile::render(self, cfg)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        self.field.dimensions()
+    }
+
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.field.to_rgb_buffer()
+    }
+}
@@ -0,0 +1,160 @@
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+use crate::automaton::Automaton;
+use crate::canvas::*;
+use crate::lifelike::{Neighborhood, Rules, ALIVE, DEAD};
+
+#[derive(Clone, Copy)]
+struct Cell {
+    alive: bool,
+}
+
+impl Colorize for Cell {
+    fn color(&self) -> Color {
+        if self.alive {
+            ALIVE
+        } else {
+            DEAD
+        }
+    }
+}
+
+/// Life-like automaton on an unbounded plane.
+/// Only live cells are stored, so memory and time depend on the population
+/// rather than on the size of the area covered.
+pub struct SparseLife {
+    rules: Rules,
+    neigh: Neighborhood,
+    live: HashSet<(i64, i64)>,
+    /// Top left corner, number of lines and columns of the area rendered
+    viewport: (i64, i64, usize, usize),
+    born: usize,
+    dead: usize,
+}
+
+impl SparseLife {
+    /// Empty plane, the viewport is a `hgt x wth` area with `(0, 0)` at its top left
+    pub fn new(hgt: usize, wth: usize, rules: &str, neigh: Neighborhood) -> Self {
+        let rules = Rules::new(rules);
+        rules.validate(neigh).unwrap_or_else(|e| panic!("{}", e));
+        assert!(!rules.born(0), "B0 rules would fill the whole plane");
+        Self {
+            rules,
+            neigh,
+            live: HashSet::new(),
+            viewport: (0, 0, hgt, wth),
+            born: 0,
+            dead: 0,
+        }
+    }
+
+    /// Move the viewport so that its top left corner is at `(i, j)`
+    pub fn set_viewport(&mut self, i: i64, j: i64) {
+        self.viewport.0 = i;
+        self.viewport.1 = j;
+    }
+
+    /// Birth cells at random with probability p in the `hgt x wth` area
+    /// whose top left corner is `(i0, j0)`
+    pub fn init_rand(&mut self, i0: i64, j0: i64, hgt: usize, wth: usize, p: f64) {
+        let mut rng = rand::thread_rng();
+        for i in i0..i0 + hgt as i64 {
+            for j in j0..j0 + wth as i64 {
+                if rng.gen::<f64>() < p {
+                    self.live.insert((i, j));
+                }
+            }
+        }
+    }
+
+    /// Set the state of a single cell
+    pub fn set(&mut self, i: i64, j: i64, alive: bool) {
+        if alive {
+            self.live.insert((i, j));
+        } else {
+            self.live.remove(&(i, j));
+        }
+    }
+
+    /// Number of live cells
+    pub fn population(&self) -> usize {
+        self.live.len()
+    }
+
+    /// Positions of the live cells, in no particular order
+    pub fn live_cells(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.live.iter().copied()
+    }
+
+    /// Calculate next generation.
+    /// Only live cells and their neighbors can be alive afterwards,
+    /// so those are the only ones considered.
+    pub fn next(&mut self) {
+        let mut counts = HashMap::new();
+        for &(i, j) in &self.live {
+            for &(di, dj) in self.neigh.offsets(i.rem_euclid(2) as usize) {
+                *counts.entry((i + di as i64, j + dj as i64)).or_insert(0) += 1;
+            }
+        }
+        let mut succ = HashSet::with_capacity(self.live.len());
+        for (&pos, &n) in &counts {
+            let alive = self.live.contains(&pos);
+            if (alive && self.rules.survives(n)) || (!alive && self.rules.born(n)) {
+                succ.insert(pos);
+            }
+        }
+        // Isolated cells are absent from `counts`
+        if self.rules.survives(0) {
+            succ.extend(self.live.iter().filter(|pos| !counts.contains_key(pos)));
+        }
+        self.born = succ.difference(&self.live).count();
+        self.dead = self.live.difference(&succ).count();
+        self.live = succ;
+    }
+
+    /// Rasterize the viewport
+    fn canvas(&self) -> Canvas<Cell> {
+        let (i0, j0, hgt, wth) = self.viewport;
+        let mut canvas = Canvas::new(hgt, wth, Cell { alive: false });
+        for &(i, j) in &self.live {
+            let (di, dj) = (i - i0, j - j0);
+            if (0..hgt as i64).contains(&di) && (0..wth as i64).contains(&dj) {
+                canvas[[di as usize, dj as usize]].alive = true;
+            }
+        }
+        canvas
+    }
+
+    /// Output the area covered by the viewport to a file
+    pub fn render(&self, cfg: &mut crate::Config) -> io::Result<()> {
+        let name = cfg.render(&self.canvas())?;
+        eprint!(
+            "\rDone generation {} : {} alive (+{} ; -{})",
+            name,
+            self.live.len(),
+            self.born,
+            self.dead
+        );
+        Ok(())
+    }
+}
+
+impl Automaton for SparseLife {
+    fn next(&mut self) {
+        SparseLife::next(self);
+    }
+
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        SparseLife::render(self, cfg)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (self.viewport.2, self.viewport.3)
+    }
+
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.canvas().to_rgb_buffer()
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// Records population statistics of a simulation as CSV,
+/// one line per generation
+pub struct StatsLogger {
+    out: BufWriter<File>,
+    /// First error encountered while writing, after which
+    /// nothing more is recorded
+    error: Option<io::Error>,
+}
+
+impl StatsLogger {
+    /// Create the file and write the header
+    pub fn new(path: &str) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "generation,population,births,deaths")?;
+        Ok(Self { out, error: None })
+    }
+
+    /// Append statistics of a single generation.
+    /// A failure to write is kept to be returned by `flush`.
+    pub fn record(&mut self, gen: usize, cnt: usize, born: usize, dead: usize) {
+        if self.error.is_none() {
+            if let Err(e) = writeln!(self.out, "{},{},{},{}", gen, cnt, born, dead) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Write all recorded statistics to the file,
+    /// or return the first error that prevented recording some of them
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => self.out.flush(),
+        }
+    }
+}
+
+impl Drop for StatsLogger {
+    fn drop(&mut self) {
+        let _ = self.out.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_csv_lines() {
+        let dir = std::env::temp_dir().join("cellular_automata_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("records_csv_lines.csv");
+        let mut logger = StatsLogger::new(path.to_str().unwrap()).unwrap();
+        logger.record(0, 5, 5, 0);
+        logger.record(1, 4, 1, 2);
+        logger.flush().unwrap();
+        let data = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            data,
+            "generation,population,births,deaths\n0,5,5,0\n1,4,1,2\n"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn write_errors_are_returned_by_flush() {
+        // Every write to /dev/full fails, once the buffer is full
+        let mut logger = StatsLogger::new("/dev/full").unwrap();
+        for gen in 0..10_000 {
+            logger.record(gen, 1000, 10, 10);
+        }
+        assert!(logger.flush().is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use std::thread;
+
+use crate::lifelike::*;
+
+/// Settings of a single run of `sweep`: a random board of `hgt` lines
+/// and `wth` columns is filled with the given density and seed,
+/// then run for at most `generations` generations.
+#[derive(Clone, Debug)]
+pub struct SweepConfig {
+    pub rule: String,
+    pub density: f64,
+    pub hgt: usize,
+    pub wth: usize,
+    pub neigh: Neighborhood,
+    pub boundary: Boundary,
+    pub generations: usize,
+    pub seed: u64,
+}
+
+/// What a board became at the end of a run of `sweep`
+#[derive(Clone, Debug)]
+pub struct SweepResult {
+    pub rule: String,
+    pub density: f64,
+    /// Number of live cells at the end of the run
+    pub population: usize,
+    /// Fraction of the cells alive at the end of the run
+    pub final_density: f64,
+    /// Number of generations calculated, fewer than requested
+    /// if the board became periodic
+    pub generations: usize,
+    pub extinct: bool,
+    /// Period of the cycle the board ended in (1 for a still life),
+    /// if it stabilized within the run
+    pub period: Option<usize>,
+}
+
+impl SweepConfig {
+    /// A 100x100 torus with the Moore neighborhood, run for
+    /// 1000 generations from seed 0. The fields can be modified afterwards.
+    pub fn new(rule: &str, density: f64) -> Self {
+        Self {
+            rule: rule.to_string(),
+            density,
+            hgt: 100,
+            wth: 100,
+            neigh: Neighborhood::Moore,
+            boundary: Boundary::Toroidal,
+            generations: 1000,
+            seed: 0,
+        }
+    }
+
+    /// Build a fresh board and run it until it becomes periodic
+    /// or `generations` generations have passed.
+    /// Fails for the same reasons as `LifeLike::try_new`.
+    pub fn run(&self) -> Result<SweepResult, RuleParseError> {
+        let mut game = LifeLike::builder()
+            .size(self.hgt, self.wth)
+            .rule(&self.rule)
+            .neighborhood(self.neigh)
+            .boundary(self.boundary)
+            .density(self.density)
+            .seed(self.seed)
+            .build()?;
+        let mut generations = 0;
+        let mut period = None;
+        while generations < self.generations && period.is_none() {
+            game.next();
+            generations += 1;
+            period = game.detect_period();
+        }
+        Ok(SweepResult {
+            rule: self.rule.clone(),
+            density: self.density,
+            population: game.population(),
+            final_density: game.density(),
+            generations,
+            extinct: game.is_extinct(),
+            period,
+        })
+    }
+}
+
+/// Run every configuration, spread over as many threads as there are
+/// processors, and collect the results in the same order.
+/// Runs are independent: the same configuration always yields
+/// the same result.
+pub fn sweep(configs: &[SweepConfig]) -> Vec<Result<SweepResult, RuleParseError>> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = configs.len().div_ceil(threads).max(1);
+    thread::scope(|s| {
+        let handles: Vec<_> = configs
+            .chunks(chunk)
+            .map(|part| s.spawn(move || part.iter().map(SweepConfig::run).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    })
+}
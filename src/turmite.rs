@@ -1,5 +1,7 @@
+use crate::automaton::Automaton;
 use crate::canvas::*;
 use rand::Rng;
+use std::io;
 
 /// Trace left by the turmites
 type Mark = usize;
@@ -95,13 +97,13 @@ impl Colorize for Mark {
     fn color(&self) -> Color {
         match self {
             0 => (0, 0, 0),
-            1 => (9, 18, 2),
-            2 => (17, 1, 25),
-            3 => (9, 19, 25),
-            4 => (25, 11, 0),
-            5 => (0, 25, 10),
-            6 => (25, 0, 0),
-            _ => (25, 25, 25),
+            1 => (92, 184, 20),
+            2 => (173, 10, 255),
+            3 => (92, 194, 255),
+            4 => (255, 112, 0),
+            5 => (0, 255, 102),
+            6 => (255, 0, 0),
+            _ => (255, 255, 255),
         }
     }
 }
@@ -162,11 +164,126 @@ impl<'a> Mound<'a> {
     }
 
     /// Create image from current state
-    pub fn render(&mut self, cfg: &mut crate::Config) {
-        let name = cfg.frame();
-        self.field.render(&name);
+    pub fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        let name = cfg.render(&self.field)?;
 
         eprint!("\rDone frame {} ({}'th movement)", name, self.cnt);
+        Ok(())
+    }
+}
+
+impl<'a> Automaton for Mound<'a> {
+    fn next(&mut self) {
+        Mound::next(self);
+    }
+
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        Mound::render(self, cfg)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        self.field.dimensions()
+    }
+
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.field.to_rgb_buffer()
+    }
+}
+
+/// A single generalized Langton's ant: each mark is associated with
+/// a turn, and the ant increments the mark it leaves the cell it stands on
+pub struct LangtonAnt {
+    hgt: usize,
+    wth: usize,
+    turns: Vec<Turn>,
+    /// Color of each mark, see `palette`
+    palette: Vec<Color>,
+    field: Canvas<Mark>,
+    ant: Turmite,
+    cnt: usize,
+}
+
+impl LangtonAnt {
+    /// The rule is a string of `R` and `L` giving the turn for each mark,
+    /// e.g. `RL` for the original ant. The ant starts at the center
+    /// facing north on a blank environment.
+    pub fn new(hgt: usize, wth: usize, rule: &str) -> Self {
+        let turns: Vec<_> = rule
+            .chars()
+            .map(|c| match c {
+                'R' | 'r' => Turn::Right,
+                'L' | 'l' => Turn::Left,
+                c => panic!("unknown turn `{}` in rule {}", c, rule),
+            })
+            .collect();
+        if turns.is_empty() {
+            panic!("the rule of an ant should have at least one turn");
+        }
+        Self {
+            hgt,
+            wth,
+            palette: Self::palette(turns.len()),
+            turns,
+            field: Canvas::new(hgt, wth, 0),
+            ant: Turmite {
+                pos: [hgt / 2, wth / 2],
+                dir: Dir::N,
+                rules: 0,
+            },
+            cnt: 0,
+        }
+    }
+
+    /// Colors of `marks` marks: black for the blank mark 0,
+    /// and hues evenly spaced around the color wheel for the others
+    /// so that rules with many turns still have distinct colors
+    fn palette(marks: usize) -> Vec<Color> {
+        let mut palette = vec![(0, 0, 0)];
+        palette.extend((1..marks).map(|k| hue((k - 1) as f64 / (marks - 1) as f64)));
+        palette
+    }
+
+    /// Turn according to the current mark, change it, and step forward
+    pub fn next(&mut self) {
+        let m = self.field[self.ant.pos];
+        self.ant.turn(self.turns[m]);
+        self.field[self.ant.pos] = (m + 1) % self.turns.len();
+        self.ant.mv(self.hgt, self.wth);
+        self.cnt += 1;
+    }
+
+    /// Make the ant advance by many steps
+    pub fn multi(&mut self, n: usize) {
+        for _ in 0..n {
+            self.next();
+        }
+    }
+
+    /// Create image from current state
+    pub fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        let name = cfg.render_with(&self.field, |&m| self.palette[m])?;
+
+        eprint!("\rDone frame {} ({}'th movement)", name, self.cnt);
+        Ok(())
+    }
+}
+
+impl Automaton for LangtonAnt {
+    fn next(&mut self) {
+        LangtonAnt::next(self);
+    }
+
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        LangtonAnt::render(self, cfg)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        self.field.dimensions()
+    }
+
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.field
+            .to_rgb_buffer_with(&Style::default(), |&m| self.palette[m])
     }
 }
 
@@ -235,3 +352,26 @@ pub const RULES_4: Rules = &[
         (0, Turn::Right),
     ],
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ant_marks_have_distinct_colors() {
+        for n in 1..=24 {
+            let palette = LangtonAnt::palette(n);
+            assert_eq!(palette.len(), n);
+            assert_eq!(palette[0], (0, 0, 0));
+            for (k, c) in palette.iter().enumerate() {
+                assert!(!palette[..k].contains(c), "{} marks: {} repeats", n, k);
+            }
+        }
+        let mut ant = LangtonAnt::new(6, 6, "RRLLLRLLLRRR");
+        ant.field[[0, 0]] = 11;
+        ant.field[[0, 1]] = 10;
+        let (_, _, buf) = ant.to_rgb_buffer();
+        assert_ne!(buf[..3], buf[3..6]);
+        assert_ne!(buf[..3], [255, 255, 255]);
+    }
+}
@@ -0,0 +1,201 @@
+use std::io;
+
+use crate::automaton::Automaton;
+use crate::canvas::*;
+use crate::lifelike::{place_plain, LoadError, Transform};
+
+/// A cell in Wireworld can have any of four states
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum State {
+    Empty,
+    Conductor,
+    Head,
+    Tail,
+}
+
+/// A single cell of a Wireworld circuit
+#[derive(Clone, Copy)]
+struct Cell {
+    curr: State,
+    /// Changing the state has to be done after all cells have been checked
+    succ: State,
+}
+
+impl Colorize for Cell {
+    fn color(&self) -> Color {
+        match self.curr {
+            State::Empty => (0, 0, 0),
+            State::Conductor => (255, 204, 0),
+            State::Head => (0, 82, 255),
+            State::Tail => (255, 31, 0),
+        }
+    }
+}
+
+/// A Wireworld circuit
+pub struct Wireworld {
+    field: Canvas<Cell>,
+    hgt: usize,
+    wth: usize,
+    heads: usize,
+}
+
+impl Wireworld {
+    /// A new circuit is empty, use `add_from_file` to build it
+    pub fn new(hgt: usize, wth: usize) -> Self {
+        Self {
+            field: Canvas::new(hgt, wth, Cell::new()),
+            hgt,
+            wth,
+            heads: 0,
+        }
+    }
+
+    /// Add a circuit stored in an external file to the canvas.
+    /// Lines starting with `!` are comments, each other character is a cell:
+    /// ```
+    /// '.' or ' ' -> State::Empty
+    /// '#' -> State::Conductor
+    /// '@' -> State::Head
+    /// '~' -> State::Tail
+    /// ```
+    pub fn add_from_file(
+        &mut self,
+        file: &str,
+        i0: isize,
+        j0: isize,
+        t: Transform,
+    ) -> Result<(), LoadError> {
+        self.add_from_str(&std::fs::read_to_string(file)?, i0, j0, t)
+    }
+
+    /// Same as `add_from_file` for a circuit given as a string
+    pub fn add_from_str(
+        &mut self,
+        data: &str,
+        i0: isize,
+        j0: isize,
+        t: Transform,
+    ) -> Result<(), LoadError> {
+        let decode = |c| match c {
+            '.' | ' ' => Some(State::Empty),
+            '#' => Some(State::Conductor),
+            '@' => Some(State::Head),
+            '~' => Some(State::Tail),
+            _ => None,
+        };
+        let field = &mut self.field;
+        place_plain(data, Some('!'), i0, j0, t, decode, |i, j, state| {
+            field.mod_idx(i, j).succ = state
+        })?;
+        self.update();
+        Ok(())
+    }
+
+    /// Actualize current state with previously calculated next state
+    /// and count electron heads
+    pub fn update(&mut self) {
+        self.heads = 0;
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                let cell = &mut self.field[[i, j]];
+                cell.update();
+                if cell.curr == State::Head {
+                    self.heads += 1;
+                }
+            }
+        }
+    }
+
+    /// Count electron heads among neighbors (with looping around the edges)
+    fn count_neigh(&self, i: usize, j: usize) -> usize {
+        let mut res = 0;
+        for mvi in -1..=1 {
+            for mvj in -1..=1 {
+                if (mvi, mvj) != (0, 0)
+                    && self.field[self.field.index_move(i, j, mvi, mvj)].curr == State::Head
+                {
+                    res += 1;
+                }
+            }
+        }
+        res
+    }
+
+    /// Rules of Wireworld for how a cell changes state
+    /// ```
+    /// State::Empty -> State::Empty
+    /// State::Head -> State::Tail
+    /// State::Tail -> State::Conductor
+    /// State::Conductor -> State::Head iff 1 or 2 neighboring heads
+    /// ```
+    pub fn next(&mut self) {
+        for i in 0..self.hgt {
+            for j in 0..self.wth {
+                let succ = match self.field[[i, j]].curr {
+                    State::Empty => State::Empty,
+                    State::Head => State::Tail,
+                    State::Tail => State::Conductor,
+                    State::Conductor => match self.count_neigh(i, j) {
+                        1 | 2 => State::Head,
+                        _ => State::Conductor,
+                    },
+                };
+                self.field[[i, j]].succ = succ;
+            }
+        }
+        self.update();
+    }
+
+    /// Current state as text, with the characters read by `add_from_str`
+    /// (except for spaces for empty cells)
+    pub fn render_ascii(&self) -> String {
+        self.field.to_ascii_with(|c| match c.curr {
+            State::Empty => ' ',
+            State::Conductor => '#',
+            State::Head => '@',
+            State::Tail => '~',
+        })
+    }
+
+    /// Output current state of the canvas as an image
+    pub fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        let name = cfg.render(&self.field)?;
+
+        eprint!("\rDone generation {} : {} electrons", name, self.heads);
+        Ok(())
+    }
+}
+
+impl Automaton for Wireworld {
+    fn next(&mut self) {
+        Wireworld::next(self);
+    }
+
+    fn render(&mut self, cfg: &mut crate::Config) -> io::Result<()> {
+        Wireworld::render(self, cfg)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        self.field.dimensions()
+    }
+
+    fn to_rgb_buffer(&self) -> (usize, usize, Vec<u8>) {
+        self.field.to_rgb_buffer()
+    }
+}
+
+impl Cell {
+    /// All cells are initialized empty
+    pub fn new() -> Self {
+        Self {
+            curr: State::Empty,
+            succ: State::Empty,
+        }
+    }
+
+    /// Set current state to calculated next state
+    pub fn update(&mut self) {
+        self.curr = self.succ;
+    }
+}